@@ -1,10 +1,78 @@
-use result::Result;
+use help::program_name;
+use provenance::Parsed;
+use result::{Error, Result};
+use schema::Schema;
+use std::env;
+use std::ffi::{OsStr, OsString};
+use std::io::Write;
+use std::process::{self, Command};
 
 /// Represents a set of command-line arguments that can be parsed.
 ///
-/// The easiest way to get this up and running is to add `#[derive(Default, Arguments)]`
-/// to the struct containing your argument data. For the derivation to work the struct may only
-/// contain types that implement `Parsable`.
+/// The easiest way to get this up and running is to add `#[derive(Arguments)]` to the struct
+/// containing your argument data. For the derivation to work the struct may only contain types
+/// that implement `Parsable`.
+///
+/// Fields that are not given are set to the `Default` of their type. A field can instead declare
+/// `#[jockey(default_value="...")]`, which is converted like a given value, or
+/// `#[jockey(required)]`, which fails with `Error::MissingArgument`. With `#[jockey(default)]` on
+/// the struct the values are taken from the `Default` implementation of the struct. Given values
+/// replace the defaults, also for fields holding several values like `Vec`.
+///
+/// Long option names are derived from the field names with `_` replaced by `-`. The struct
+/// attribute `#[jockey(rename_all="...")]` selects another convention, one of "kebab-case",
+/// "snake_case", "camelCase" or "lowercase". A `long_option` on a field takes precedence. The rule
+/// also names the values of enums deriving `Parsable` unless the enum sets its own `rename_all`.
+/// Two fields may not end up with the same option. With `#[jockey(allow_abbrev)]` long options may
+/// be abbreviated to any unique prefix.
+///
+/// Long options start with "--" and short ones with "-". `#[jockey(option_style="single_dash")]`
+/// on the struct makes long options start with "-" like in "-name value". Other prefixes, e.g.
+/// "+" or "/", can be set with `#[jockey(long_prefix="...", short_prefix="...")]`. The automatic
+/// help and version options and the help text follow the prefixes. With a single character prefix
+/// the value of a long option may also be glued to it like in "-Xmx1g".
+///
+/// `#[jockey(ignore_case)]` matches long options ignoring case, on the struct for all options and
+/// on a field for its option. It also matches the values against the possible values ignoring
+/// case, including the values of enums. Enums deriving `Parsable` accept `#[jockey(ignore_case)]`
+/// to do so wherever they are used.
+///
+/// Options are matched anywhere in the arguments. With `#[jockey(stop_at_first_positional)]` on the
+/// struct they are only matched up to the first argument that does not look like one. The
+/// following arguments are positional or unknown arguments, as in "tool run cmd --cmd-flag".
+/// `#[jockey(posixly_correct)]` does so only if the `POSIXLY_CORRECT` environment variable is set.
+///
+/// A `Vec<String>` or `Vec<OsString>` field with `#[jockey(trailing_var_arg)]` takes all arguments
+/// after "--" verbatim, e.g. the command and its arguments in "tool --verbose -- cmd --flag". With
+/// `#[jockey(trailing_var_arg, position=N)]` it also takes everything from position `N` on. These
+/// arguments are neither matched as options nor added to the `unknown_args` field.
+///
+/// A `bool` field is a flag that is set by "--flag". It also takes an attached "--flag=true" or
+/// "--flag=false", so an `Option<bool>` field tells an explicit `false` from a flag not given.
+///
+/// Values that look like options are not taken: "--name -x" fails with `Error::MissingValues` and
+/// "-x" is not taken as a positional argument. This holds whether or not "-x" is a known option, so
+/// "--name --foo" fails the same way even if there is no "--foo". Numeric fields take negative
+/// numbers like "-5" unless there is a short option with that digit.
+/// `#[jockey(allow_hyphen_values)]` on a field, or on the struct for all fields, takes any value,
+/// also the name of another option. Attached values like "--name=-x" always work.
+///
+/// An option that takes a single value fails with `Error::DuplicateOption` if it is given again,
+/// also under another of its names like "--foo a -f b". `#[jockey(duplicate_options="...")]` on a
+/// field, or on the struct for all fields, selects another policy: "error", "first_wins" or
+/// "last_wins". Fields that collect values like `Vec` may always be given several times.
+///
+/// `parse_args_with_meta` parses like `parse_args` and also records for every field whether its
+/// value was given or is a default, along with the indices of the arguments it was taken from.
+///
+/// Rules that involve several fields can be checked by a function given with
+/// `#[jockey(validate="...")]` on the struct. It takes the parsed struct and returns a `Result`
+/// whose error converts into `Error`, e.g. a `String` which becomes `Error::Custom`.
+///
+/// "-h" and "--help" fail with `Error::HelpRequested`, "-V" and "--version" with
+/// `Error::VersionRequested`, which `parse_or_exit` turns into the help text and the version. Each
+/// is only added if no field uses the name. Structs with an `unknown_args` field get none of them,
+/// so these arguments are collected like any other unknown option.
 ///
 /// # Example
 ///
@@ -12,7 +80,7 @@ use result::Result;
 /// # extern crate jockey;
 /// # #[macro_use] extern crate jockey_derive;
 /// # fn main() {
-/// #[derive(Arguments, Default)]
+/// #[derive(Arguments, ToArgs)]
 /// struct MyArguments {
 ///
 ///     // Is set if "--my-string <value>" or "--my-string=<value>" is given.
@@ -25,10 +93,18 @@ use result::Result;
 ///     // A flag which is set to true if "--flag" (without a value) is given.
 ///     pub flag: bool,
 ///
+///     // Is 8080 unless "--port <value>" is given.
+///     #[jockey(default_value="8080")]
+///     pub port: std::num::NonZeroU16,
+///
 ///     // Argument where the long option name is customized.
 ///     #[jockey(long_option="other-name")]
 ///     pub renamed: Option<String>,
 ///
+///     // Collects "--tag a,b --tag c" as three elements. "\," stands for a literal comma.
+///     #[jockey(value_delimiter=',')]
+///     pub tag: Vec<String>,
+///
 ///     // A catch-all where all otherwise unrecognized command-line options will be stored.
 ///     #[jockey(unknown_args)]
 ///     pub argn: Vec<String>,
@@ -39,10 +115,11 @@ use result::Result;
 ///     "-s", "value2",
 ///     "--flag",
 ///     "--other-name", "value3",
+///     "--tag", "a,b", "--tag", "c",
 ///     "./file1", "./file2"];
 /// let args_iter = args_vec.iter().map(|x| x.to_string());
 ///
-/// use jockey::Arguments;
+/// use jockey::{Arguments, ToArgs};
 ///
 /// // Parse the arguments. A real application would use env::args() instead of args_iter.
 /// let args = MyArguments::parse_args(args_iter).unwrap();
@@ -50,15 +127,155 @@ use result::Result;
 /// assert_eq!(args.my_string, Some("value1".into()));
 /// assert_eq!(args.with_short_opt, Some("value2".into()));
 /// assert_eq!(args.flag, true);
+/// assert_eq!(args.port.get(), 8080);
 /// assert_eq!(args.renamed, Some("value3".into()));
+/// assert_eq!(args.tag, vec!["a".to_string(), "b".to_string(), "c".to_string()]);
 /// assert_eq!(args.argn, vec!["./file1".to_string(), "./file2".to_string()]);
+///
+/// // The parsed arguments can be turned back into an equivalent arguments vector.
+/// let mut again = vec!["/path/to/my/executable".to_string()];
+/// again.extend(args.to_args());
+/// let args2 = MyArguments::parse_args(again.into_iter()).unwrap();
+/// assert_eq!(args2.tag, args.tag);
+/// assert_eq!(args2.argn, args.argn);
 /// # }
 /// ```
-pub trait Arguments : Sized + Default {
+pub trait Arguments : Sized {
 
     /// Parses command-line arguments.
     ///
     /// This function expects an iterator as supplied by `env::args()`. The first element which
-    /// typically contains the path of the executable is ignored.
+    /// typically contains the path of the executable is ignored. The result is checked with
+    /// `validate` before it is returned.
     fn parse_args<I> (args: I) -> Result<Self> where I : Iterator<Item = String>;
+
+    /// Parses command-line arguments like `parse_args` and records for every field where its
+    /// value came from and which arguments it was taken from.
+    ///
+    /// The default implementation records no fields.
+    fn parse_args_with_meta<I> (args: I) -> Result<Parsed<Self>> where I : Iterator<Item = String> {
+        Ok(Parsed::new(Self::parse_args(args)?, &[], &[]))
+    }
+
+    /// Parses command-line arguments on top of the current values.
+    ///
+    /// Only the options that are given are applied, all other fields keep their values. Fields
+    /// holding several values like `Vec` are extended following `ParsableWithOption::assign`, maps
+    /// with `duplicate_keys` are merged following their policy.
+    /// Nothing is changed if an error is returned. The result is not validated, call `validate`
+    /// once all values are in place.
+    fn update_from_args<I> (&mut self, args: I) -> Result<()> where I : Iterator<Item = String>;
+
+    /// Checks rules that involve several fields.
+    ///
+    /// The default implementation accepts everything.
+    fn validate(&self) -> Result<()> {
+        Ok(())
+    }
+
+    /// Describes the options and positional arguments.
+    ///
+    /// The usage line, the help text and the version are rendered from it. The derived
+    /// implementation uses the field types, attributes and doc comments, as well as
+    /// `#[jockey(name="...", version="...", about="...")]` of the struct. The version defaults to
+    /// the version of the crate. `Schema::to_json` exports it for other tools.
+    ///
+    /// Options have a long and a short name but no further aliases, and neither environment
+    /// variables nor subcommands exist in this crate, so the schema has no fields for them. Aliases
+    /// of enum values are listed among the possible values.
+    fn schema() -> Schema {
+        Schema::default()
+    }
+
+    /// Returns the usage line, e.g. "Usage: program [OPTIONS] <FILE>".
+    fn usage(program: &str) -> String {
+        Self::schema().usage(program)
+    }
+
+    /// Returns the help text that is shown for "--help".
+    fn help(program: &str) -> String {
+        Self::schema().help(program)
+    }
+
+    /// Returns the line that is shown for "--version", e.g. "program 1.0.0".
+    fn version(program: &str) -> String {
+        Self::schema().version(program)
+    }
+
+    /// Returns a bash completion script that completes the options and their possible values.
+    fn bash_completion(program: &str) -> String {
+        Self::schema().bash_completion(program)
+    }
+
+    /// Parses the arguments of the process and exits if that fails.
+    ///
+    /// The help text and the version are printed to stdout with exit code 0. Errors are printed
+    /// to stderr along with the usage line and the process exits with code 2.
+    fn parse_or_exit() -> Self {
+        match Self::parse_or_report(env::args(), &mut std::io::stdout(), &mut std::io::stderr()) {
+            Ok(result) => result,
+            Err(code) => process::exit(code),
+        }
+    }
+
+    /// Like `parse_or_exit` but writes to `out` and `err` and returns the exit code instead of
+    /// exiting.
+    fn parse_or_report<I, O, E>(args: I, out: &mut O, err: &mut E) -> std::result::Result<Self, i32>
+        where I: Iterator<Item = String>, O: Write, E: Write
+    {
+        let args: Vec<String> = args.collect();
+        let program = program_name(args.first().map(|arg| arg.as_str()).unwrap_or_default());
+
+        // Nothing sensible can be done if the output cannot be written, so errors are ignored.
+        match Self::parse_args(args.into_iter()) {
+            Ok(result) => Ok(result),
+            Err(Error::HelpRequested) => {
+                let _ = write!(out, "{}", Self::help(&program));
+                Err(0)
+            },
+            Err(Error::VersionRequested) => {
+                let _ = writeln!(out, "{}", Self::version(&program));
+                Err(0)
+            },
+            Err(error) => {
+                let _ = writeln!(err, "{}: {}", program, error);
+                let _ = writeln!(err, "{}", Self::usage(&program));
+                let _ = writeln!(err, "Try '{} --help' for more information.", program);
+                Err(2)
+            },
+        }
+    }
+
+}
+
+/// Represents a set of command-line arguments that can be formatted back into arguments.
+///
+/// It is derived with `#[derive(ToArgs)]` next to `#[derive(Arguments)]` and takes the same
+/// attributes. The field types have to implement `FormattableWithOption` or
+/// `FormattableWithPosition`, or `FormattableValue` for fields with conversion attributes.
+pub trait ToArgs {
+
+    /// Formats the arguments back into a canonical arguments vector.
+    ///
+    /// The vector does not contain the path of the executable. Passing it to `parse_args` behind
+    /// one yields arguments equal to `self`. Positional arguments come first, followed by the
+    /// options in field order and the unknown arguments.
+    ///
+    /// Trailing `Vec<OsString>` fields that are not valid UTF-8 are converted lossily, see
+    /// `to_os_args`.
+    fn to_args(&self) -> Vec<String>;
+
+    /// Formats the arguments like `to_args` but keeps trailing `Vec<OsString>` fields as they are.
+    ///
+    /// The default implementation converts the result of `to_args`.
+    fn to_os_args(&self) -> Vec<OsString> {
+        self.to_args().into_iter().map(OsString::from).collect()
+    }
+
+    /// Creates a `Command` that runs `program` with the arguments returned by `to_os_args`.
+    fn to_command<S: AsRef<OsStr>>(&self, program: S) -> Command {
+        let mut command = Command::new(program);
+        command.args(self.to_os_args());
+        command
+    }
 }
@@ -0,0 +1,846 @@
+use matching::{check_builtin_option, check_duplicate_option, check_option_values, expand_abbreviation, is_option_like, is_value_like, posixly_correct, rewrite_next_arg};
+use parsable::{DuplicateOptions, ParsableValue, ParsableWithOption, ParseResult};
+use parsable::{parse_default_with, parse_option_values, parse_option_with, parse_position_with};
+use result::{Error, Result};
+use schema::{ArgumentSchema, OptionSchema, Schema};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::iter::Peekable;
+
+/// Type of the values an option or positional argument takes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ValueType {
+    String,
+    Integer,
+    Float,
+}
+
+impl ValueType {
+    /// Convert a single value.
+    pub fn parse(self, value: &str) -> std::result::Result<Value, String> {
+        match self {
+            ValueType::String => Ok(Value::String(value.to_string())),
+            ValueType::Integer => i64::parse_value(value).map(Value::Integer),
+            ValueType::Float => f64::parse_value(value).map(Value::Float),
+        }
+    }
+}
+
+/// A value parsed by `Command::parse`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    /// A flag that was given.
+    Flag,
+    String(String),
+    Integer(i64),
+    Float(f64),
+}
+
+impl Value {
+    /// The value if it is a string.
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Value::String(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// The value if it is an integer.
+    pub fn as_integer(&self) -> Option<i64> {
+        match *self {
+            Value::Integer(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// The value if it is a number. Integers are converted.
+    pub fn as_float(&self) -> Option<f64> {
+        match *self {
+            Value::Integer(value) => Some(value as f64),
+            Value::Float(value) => Some(value),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Value::Flag => write!(f, "true"),
+            Value::String(value) => write!(f, "{}", value),
+            Value::Integer(value) => write!(f, "{}", value),
+            Value::Float(value) => write!(f, "{}", value),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Fallback {
+    None,
+    Required,
+    DefaultValue(String),
+}
+
+/// Specification of an option, flag or positional argument of a `Command`.
+///
+/// The name identifies the values in `Matches`. Options and flags are matched as "--name"
+/// unless another long name is set.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OptSpec {
+    name: String,
+    long: Option<String>,
+    short: Option<String>,
+    position: Option<usize>,
+    value_type: Option<ValueType>,
+    num_values: usize,
+    multiple: bool,
+    duplicate_options: Option<DuplicateOptions>,
+    allow_hyphen_values: bool,
+    fallback: Fallback,
+    help: String,
+    // Misuse of the builder, reported by Command::parse.
+    errors: Vec<String>,
+}
+
+impl OptSpec {
+    fn new(name: &str, value_type: Option<ValueType>, position: Option<usize>) -> Self {
+        OptSpec {
+            name: name.to_string(),
+            long: if position.is_none() { Some(format!("--{}", name)) } else { None },
+            short: None,
+            position,
+            value_type,
+            num_values: 1,
+            multiple: false,
+            duplicate_options: None,
+            allow_hyphen_values: false,
+            fallback: Fallback::None,
+            help: String::new(),
+            errors: Vec::new(),
+        }
+    }
+
+    /// An option taking a string value, e.g. "--name <value>" or "--name=<value>".
+    pub fn option(name: &str) -> Self {
+        OptSpec::new(name, Some(ValueType::String), None)
+    }
+
+    /// An option without a value like "--name".
+    pub fn flag(name: &str) -> Self {
+        OptSpec::new(name, None, None)
+    }
+
+    /// A positional argument taking a string value.
+    ///
+    /// The position counts from the path of the executable, so the first argument is 1.
+    pub fn positional(name: &str, position: usize) -> Self {
+        OptSpec::new(name, Some(ValueType::String), Some(position))
+    }
+
+    /// Sets the long option name, e.g. "--other-name".
+    pub fn long(mut self, long: &str) -> Self {
+        self.long = Some(long.to_string());
+        self
+    }
+
+    /// Sets the short option name, e.g. "s" for "-s".
+    pub fn short(mut self, short: &str) -> Self {
+        self.short = Some(format!("-{}", short));
+        self
+    }
+
+    /// Sets the type of the values. Flags take no values, `Command::parse` fails with
+    /// `Error::InvalidDefinition` if one is set for a flag.
+    pub fn value_type(mut self, value_type: ValueType) -> Self {
+        if self.value_type.is_none() {
+            self.errors.push(format!("Flag {} cannot take values", self.name));
+            return self;
+        }
+        self.value_type = Some(value_type);
+        self
+    }
+
+    /// Sets the number of values following each occurrence of an option, like for tuple fields.
+    /// `Command::parse` fails with `Error::InvalidDefinition` for zero.
+    pub fn num_values(mut self, num_values: usize) -> Self {
+        if num_values == 0 {
+            self.errors.push(format!("Option {} must take at least one value", self.name));
+            return self;
+        }
+        self.num_values = num_values;
+        self
+    }
+
+    /// Allows the option to be given several times. The values of all occurrences are collected.
+    pub fn multiple(mut self) -> Self {
+        self.multiple = true;
+        self
+    }
+
+    /// Sets what happens if the option is given again without `multiple`, overriding
+    /// `Command::duplicate_options`.
+    pub fn duplicate_options(mut self, policy: DuplicateOptions) -> Self {
+        self.duplicate_options = Some(policy);
+        self
+    }
+
+    /// Takes values that look like options, e.g. "--pattern -x". Without this such a value fails
+    /// with `Error::MissingValues` and a positional argument is not taken. Negative numbers are
+    /// taken for integers and floats anyway.
+    pub fn allow_hyphen_values(mut self) -> Self {
+        self.allow_hyphen_values = true;
+        self
+    }
+
+    /// Fails with `Error::MissingArgument` if the option or argument is not given.
+    pub fn required(mut self) -> Self {
+        self.fallback = Fallback::Required;
+        self
+    }
+
+    /// Sets the value that is used if the option or argument is not given.
+    pub fn default_value(mut self, value: &str) -> Self {
+        self.fallback = Fallback::DefaultValue(value.to_string());
+        self
+    }
+
+    /// Sets the description shown in the help.
+    pub fn help(mut self, help: &str) -> Self {
+        self.help = help.to_string();
+        self
+    }
+
+    /// Name in error messages, like the derived parsers use.
+    fn display_name(&self) -> String {
+        match self.position {
+            Some(position) => format!("argument {}", position),
+            None => self.long.as_ref().or(self.short.as_ref()).unwrap().clone(),
+        }
+    }
+
+    fn value_name(&self) -> String {
+        self.name.to_uppercase().replace('-', "_")
+    }
+
+    /// Whether `value` can be taken as a value, see `is_value_like`.
+    fn takes_value(&self, value: &str, options: &[&str]) -> bool {
+        let numeric = self.value_type == Some(ValueType::Integer) || self.value_type == Some(ValueType::Float);
+        self.allow_hyphen_values || is_value_like(value, &["--", "-"], numeric, options)
+    }
+
+    /// Checks the argument `arg` at `index` of `argv` and the values following it before it is
+    /// parsed.
+    ///
+    /// Returns whether the argument may be taken. Fails if the argument is this option and one of
+    /// its values looks like an option.
+    fn check_values(&self, index: usize, arg: &str, argv: &[String], options: &[&str]) -> Result<bool> {
+        if self.position.is_some() {
+            return Ok(self.position != Some(index) || self.takes_value(arg, options));
+        }
+        if self.value_type.is_some() && (self.long.as_deref() == Some(arg) || self.short.as_deref() == Some(arg)) {
+            check_option_values(arg, &argv[index + 1..], self.num_values, |_, value| self.takes_value(value, options))?;
+        }
+        Ok(true)
+    }
+
+    fn parse_arg<I>(&self, iter: &mut Peekable<I>) -> ParseResult<Vec<Value>>
+        where I: Iterator<Item = (usize, String)>
+    {
+        if let Some(position) = self.position {
+            let value_type = self.value_type.unwrap();
+            return parse_position_with(iter, position, |value| value_type.parse(value)).map(|value| vec![value]);
+        }
+        for option in self.long.iter().chain(self.short.iter()) {
+            let result = match self.value_type {
+                None => <bool as ParsableWithOption>::parse_arg(iter, option).map(|set| if set { vec![Value::Flag] } else { vec![] }),
+                Some(value_type) if self.num_values == 1 => {
+                    parse_option_with(iter, option, |value| value_type.parse(value)).map(|value| vec![value])
+                },
+                Some(value_type) => {
+                    let result = parse_option_values(iter, option, self.num_values);
+                    match result.parsed {
+                        Some(Ok(values)) => {
+                            let mut converted = Vec::with_capacity(values.len());
+                            for value in values {
+                                match value_type.parse(&value) {
+                                    Ok(val) => converted.push(val),
+                                    Err(reason) => return ParseResult::err(Error::InvalidValue(option.to_string(), value, reason)),
+                                }
+                            }
+                            ParseResult::success(converted, result.blacklist)
+                        },
+                        Some(Err(err)) => ParseResult::err(err),
+                        None => ParseResult::none(),
+                    }
+                },
+            };
+            if result.parsed.is_some() {
+                return if self.multiple { ParseResult { blacklist: None, ..result } } else { result };
+            }
+        }
+        ParseResult::none()
+    }
+
+    fn get_fallback(&self) -> Result<Vec<Value>> {
+        match self.fallback {
+            Fallback::None => Ok(vec![]),
+            Fallback::Required => Err(Error::MissingArgument(self.display_name())),
+            Fallback::DefaultValue(ref value) => match self.value_type {
+                Some(value_type) => Ok(vec![parse_default_with(&self.display_name(), value, |value| value_type.parse(value))?]),
+                None => Ok(vec![]),
+            },
+        }
+    }
+}
+
+/// The values parsed by `Command::parse`, looked up by the names of the `OptSpec`s.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Matches {
+    values: HashMap<String, Vec<Value>>,
+    unknown: Vec<String>,
+}
+
+impl Matches {
+    /// Whether a value was given or defaulted for `name`.
+    pub fn contains(&self, name: &str) -> bool {
+        self.values.contains_key(name)
+    }
+
+    /// The last value of `name`.
+    pub fn value(&self, name: &str) -> Option<&Value> {
+        self.values(name).last()
+    }
+
+    /// All values of `name` in the order they were given.
+    pub fn values(&self, name: &str) -> &[Value] {
+        self.values.get(name).map(|values| values.as_slice()).unwrap_or(&[])
+    }
+
+    /// Whether the flag `name` was given.
+    pub fn flag(&self, name: &str) -> bool {
+        self.contains(name)
+    }
+
+    /// How often the flag or option `name` was given.
+    pub fn occurrences(&self, name: &str) -> usize {
+        self.values(name).len()
+    }
+
+    /// The last value of `name` if it is a string.
+    pub fn string(&self, name: &str) -> Option<&str> {
+        self.value(name).and_then(|value| value.as_str())
+    }
+
+    /// The last value of `name` if it is an integer.
+    pub fn integer(&self, name: &str) -> Option<i64> {
+        self.value(name).and_then(|value| value.as_integer())
+    }
+
+    /// The last value of `name` if it is a number.
+    pub fn float(&self, name: &str) -> Option<f64> {
+        self.value(name).and_then(|value| value.as_float())
+    }
+
+    /// The arguments that did not match any `OptSpec`, see `Command::allow_unknown`.
+    pub fn unknown_args(&self) -> &[String] {
+        &self.unknown
+    }
+}
+
+/// A parser whose options are defined at runtime, e.g. from a configuration file.
+///
+/// It behaves like a parser derived with `#[derive(Arguments)]` and fails with the same errors.
+///
+/// # Example
+///
+/// ```
+/// use jockey::{Command, OptSpec, ValueType};
+///
+/// let command = Command::new("plugin")
+///     .arg(OptSpec::option("level").short("l").value_type(ValueType::Integer).default_value("1"))
+///     .arg(OptSpec::flag("verbose"))
+///     .arg(OptSpec::option("tag").multiple())
+///     .arg(OptSpec::positional("input", 1).required());
+///
+/// let args = vec!["plugin", "in.txt", "-l", "3", "--tag", "a", "--tag=b"];
+/// let matches = command.parse(args.iter().map(|x| x.to_string())).unwrap();
+///
+/// assert_eq!(matches.integer("level"), Some(3));
+/// assert_eq!(matches.flag("verbose"), false);
+/// assert_eq!(matches.string("input"), Some("in.txt"));
+/// assert_eq!(matches.values("tag").len(), 2);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Command {
+    name: String,
+    version: Option<String>,
+    about: Option<String>,
+    specs: Vec<OptSpec>,
+    allow_unknown: bool,
+    allow_abbrev: bool,
+    // None reads the POSIXLY_CORRECT environment variable.
+    stop_at_first_positional: Option<bool>,
+    duplicate_options: DuplicateOptions,
+    // Misuse of the builder, reported by parse.
+    errors: Vec<String>,
+}
+
+impl Command {
+    /// Creates a command without any options. `name` is used in the usage line.
+    pub fn new(name: &str) -> Self {
+        Command {
+            name: name.to_string(),
+            version: None,
+            about: None,
+            specs: Vec::new(),
+            allow_unknown: false,
+            allow_abbrev: false,
+            stop_at_first_positional: Some(false),
+            duplicate_options: DuplicateOptions::Error,
+            errors: Vec::new(),
+        }
+    }
+
+    /// Sets the version, which enables "-V" and "--version".
+    pub fn version(mut self, version: &str) -> Self {
+        self.version = Some(version.to_string());
+        self
+    }
+
+    /// Sets the text shown above the usage line in the help.
+    pub fn about(mut self, about: &str) -> Self {
+        self.about = Some(about.to_string());
+        self
+    }
+
+    /// Adds an option, flag or positional argument.
+    ///
+    /// `parse` fails with `Error::InvalidDefinition` if its name or one of its option names is
+    /// already used. Such an argument is not added.
+    pub fn arg(mut self, spec: OptSpec) -> Self {
+        self.errors.extend(spec.errors.iter().cloned());
+        if self.specs.iter().any(|other| other.name == spec.name) {
+            self.errors.push(format!("Argument {} is defined twice", spec.name));
+            return self;
+        }
+        for option in spec.long.iter().chain(spec.short.iter()) {
+            if self.has_option(option) {
+                self.errors.push(format!("Option {} is defined twice", option));
+                return self;
+            }
+        }
+        self.specs.push(spec);
+        self
+    }
+
+    /// Collects arguments that match no option in `Matches::unknown_args` instead of failing with
+    /// `Error::UnknownOption`. The built-in "--help" and "--version" are collected as well.
+    pub fn allow_unknown(mut self) -> Self {
+        self.allow_unknown = true;
+        self
+    }
+
+    /// Accepts unique prefixes of long options like "--verb" for "--verbose", see
+    /// `expand_abbreviation`.
+    pub fn allow_abbrev(mut self) -> Self {
+        self.allow_abbrev = true;
+        self
+    }
+
+    /// Stops matching options at the first argument that does not look like one. All following
+    /// arguments are positional or unknown arguments.
+    ///
+    /// Without this options are matched anywhere in the arguments.
+    pub fn stop_at_first_positional(mut self) -> Self {
+        self.stop_at_first_positional = Some(true);
+        self
+    }
+
+    /// Matches options anywhere in the arguments. This is the default.
+    pub fn permute(mut self) -> Self {
+        self.stop_at_first_positional = Some(false);
+        self
+    }
+
+    /// Stops matching options at the first positional argument only if the `POSIXLY_CORRECT`
+    /// environment variable is set when parsing.
+    pub fn posixly_correct(mut self) -> Self {
+        self.stop_at_first_positional = None;
+        self
+    }
+
+    /// Sets what happens if an option without `multiple` is given again, also under another of its
+    /// names. Fails with `Error::DuplicateOption` unless set.
+    pub fn duplicate_options(mut self, policy: DuplicateOptions) -> Self {
+        self.duplicate_options = policy;
+        self
+    }
+
+    fn has_option(&self, name: &str) -> bool {
+        self.specs.iter().any(|spec| spec.long.as_deref() == Some(name) || spec.short.as_deref() == Some(name))
+    }
+
+    /// Built-in options that are not taken by an `OptSpec`, none if unknown arguments are allowed.
+    fn builtin_options(&self, names: &[&'static str]) -> Vec<&'static str> {
+        if self.allow_unknown {
+            return vec![];
+        }
+        names.iter().cloned().filter(|name| !self.has_option(name)).collect()
+    }
+
+    fn version_options(&self) -> Vec<&'static str> {
+        if self.version.is_none() {
+            return vec![];
+        }
+        self.builtin_options(&["-V", "--version"])
+    }
+
+    /// Fails with `Error::InvalidDefinition` if the builder was misused, e.g. an option was
+    /// defined twice. Useful to check options that are read at runtime before parsing.
+    pub fn check_definition(&self) -> Result<()> {
+        match self.errors.first() {
+            Some(error) => Err(Error::InvalidDefinition(error.clone())),
+            None => Ok(()),
+        }
+    }
+
+    /// Parses command-line arguments as supplied by `env::args()`.
+    ///
+    /// The first element which typically contains the path of the executable is ignored. Fails
+    /// with `Error::InvalidDefinition` if `check_definition` does.
+    pub fn parse<I>(&self, args: I) -> Result<Matches> where I: Iterator<Item = String> {
+        self.check_definition()?;
+        let help_options = self.builtin_options(&["-h", "--help"]);
+        let version_options = self.version_options();
+        let long_options: Vec<&str> = self.specs.iter().filter_map(|spec| spec.long.as_deref())
+            .chain(help_options.iter().chain(version_options.iter()).cloned().filter(|name| name.starts_with("--")))
+            .collect();
+        let options: Vec<&str> = self.specs.iter().flat_map(|spec| spec.long.iter().chain(spec.short.iter())).map(|name| name.as_str())
+            .chain(help_options.iter().chain(version_options.iter()).cloned())
+            .collect();
+
+        let stop_at_first_positional = self.stop_at_first_positional.unwrap_or_else(posixly_correct);
+        let mut options_done = false;
+
+        let mut matches = Matches::default();
+        // Names of the specs that were given and may only be given once.
+        let mut given: HashSet<&str> = HashSet::new();
+        // Collected so that the values after an option can be checked before it is parsed.
+        let argv: Vec<String> = args.collect();
+        let mut iter = argv.iter().cloned().enumerate().peekable();
+
+        // Skip first argument which is the executable path.
+        iter.next();
+
+        'args: loop {
+            if self.allow_abbrev && !options_done {
+                rewrite_next_arg(&mut iter, |arg| expand_abbreviation(arg, "--", &long_options))?;
+            }
+
+            let arg = match iter.peek() {
+                Some((_, arg)) => arg,
+                None => break,
+            };
+            if stop_at_first_positional && !is_option_like(arg, &["--", "-"]) {
+                options_done = true;
+            }
+            if !options_done {
+                check_builtin_option(arg, &help_options, &version_options)?;
+            }
+
+            for spec in self.specs.iter().filter(|spec| !options_done || spec.position.is_some()) {
+                let (index, arg) = match iter.peek() {
+                    Some((index, arg)) => (*index, arg.clone()),
+                    None => break,
+                };
+                // Once options are no longer matched any argument is taken as a positional one.
+                if !options_done && !spec.check_values(index, &arg, &argv, &options)? {
+                    continue;
+                }
+                let result = spec.parse_arg(&mut iter);
+                let policy = spec.duplicate_options.unwrap_or(self.duplicate_options);
+                match result.parsed {
+                    Some(Ok(values)) => {
+                        if !check_duplicate_option(&mut given, &spec.name, result.blacklist.as_ref(), policy)? {
+                            continue 'args;
+                        }
+                        let entry = matches.values.entry(spec.name.clone()).or_default();
+                        if !spec.multiple {
+                            entry.clear();
+                        }
+                        entry.extend(values);
+                        // A flag given like "--flag=false" is not set.
+                        if entry.is_empty() {
+                            matches.values.remove(&spec.name);
+                        }
+                        continue 'args;
+                    },
+                    Some(Err(err)) => return Err(err),
+                    None => {},
+                }
+            }
+
+            match iter.next() {
+                Some((_, arg)) if self.allow_unknown => matches.unknown.push(arg),
+                Some((_, arg)) => return Err(Error::UnknownOption(arg)),
+                None => {},
+            }
+        }
+
+        for spec in self.specs.iter() {
+            if !matches.values.contains_key(&spec.name) {
+                let values = spec.get_fallback()?;
+                if !values.is_empty() {
+                    matches.values.insert(spec.name.clone(), values);
+                }
+            }
+        }
+        Ok(matches)
+    }
+
+    /// Describes the options and positional arguments like `Arguments::schema`.
+    pub fn schema(&self) -> Schema {
+        let mut options = Vec::new();
+        let mut arguments = Vec::new();
+        for spec in self.specs.iter() {
+            let type_name = match spec.value_type {
+                None => "bool",
+                Some(ValueType::String) => "String",
+                Some(ValueType::Integer) => "i64",
+                Some(ValueType::Float) => "f64",
+            };
+            let default_value = match spec.fallback {
+                Fallback::DefaultValue(ref value) => Some(value.clone()),
+                _ => None,
+            };
+            if spec.position.is_some() {
+                arguments.push(ArgumentSchema {
+                    name: spec.value_name(),
+                    position: spec.position,
+                    type_name: type_name.to_string(),
+                    required: spec.fallback == Fallback::Required,
+                    default_value,
+                    possible_values: vec![],
+                    range: None,
+                    trailing: false,
+                    help: spec.help.clone(),
+                });
+                continue;
+            }
+            let value_names = match spec.value_type {
+                Some(_) => (0..spec.num_values).map(|_| spec.value_name()).collect(),
+                None => vec![],
+            };
+            options.push(OptionSchema {
+                long: spec.long.clone(),
+                short: spec.short.clone(),
+                value_names,
+                optional_value: false,
+                type_name: type_name.to_string(),
+                multiple: spec.multiple,
+                required: spec.fallback == Fallback::Required,
+                default_value,
+                possible_values: vec![],
+                range: None,
+                help: spec.help.clone(),
+            });
+        }
+        arguments.sort_by_key(|argument| argument.position);
+        if self.allow_unknown {
+            arguments.push(ArgumentSchema {
+                name: String::from("ARGS"),
+                type_name: String::from("Vec<String>"),
+                ..ArgumentSchema::default()
+            });
+        }
+
+        for &(ref names, help) in [(self.builtin_options(&["-h", "--help"]), "Print help"), (self.version_options(), "Print version")].iter() {
+            if names.is_empty() {
+                continue;
+            }
+            options.push(OptionSchema {
+                long: names.iter().find(|name| name.starts_with("--")).map(|name| name.to_string()),
+                short: names.iter().find(|name| !name.starts_with("--")).map(|name| name.to_string()),
+                type_name: String::from("bool"),
+                help: help.to_string(),
+                ..OptionSchema::default()
+            });
+        }
+
+        Schema {
+            name: Some(self.name.clone()),
+            version: self.version.clone(),
+            about: self.about.clone(),
+            options,
+            arguments,
+        }
+    }
+}
+
+#[cfg(test)]
+fn parse_command(command: &Command, args: &[&str]) -> Result<Matches> {
+    command.parse(Some("dummy").iter().chain(args.iter()).map(|x| x.to_string()))
+}
+
+#[test]
+pub fn test_command_parse() {
+    let command = Command::new("plugin")
+        .arg(OptSpec::option("size").value_type(ValueType::Integer).num_values(2))
+        .arg(OptSpec::option("scale").value_type(ValueType::Float).default_value("1.5"))
+        .arg(OptSpec::flag("verbose").short("v").multiple())
+        .arg(OptSpec::option("name"))
+        .arg(OptSpec::positional("input", 1))
+        .allow_unknown();
+
+    let matches = parse_command(&command, &["in", "--size", "800", "600", "-v", "--verbose", "--name=x", "rest"]).unwrap();
+    assert_eq!(matches.values("size"), &[Value::Integer(800), Value::Integer(600)]);
+    assert_eq!(matches.float("scale"), Some(1.5));
+    assert_eq!(matches.occurrences("verbose"), 2);
+    assert_eq!(matches.string("name"), Some("x"));
+    assert_eq!(matches.string("input"), Some("in"));
+    assert_eq!(matches.unknown_args(), &["rest".to_string()]);
+
+    let matches = parse_command(&command, &[]).unwrap();
+    assert!(!matches.contains("input"));
+    assert!(!matches.flag("verbose"));
+
+    let matches = parse_command(&command, &["--verbose=false"]).unwrap();
+    assert!(!matches.flag("verbose"));
+}
+
+#[test]
+pub fn test_command_errors() {
+    let command = Command::new("plugin")
+        .arg(OptSpec::option("level").value_type(ValueType::Integer))
+        .arg(OptSpec::option("mode").required())
+        .version("1.0");
+
+    assert_eq!(parse_command(&command, &["--mode", "a", "--level=x"]), Err(Error::InvalidValue("--level".into(), "x".into(), "invalid digit found in string".into())));
+    assert_eq!(parse_command(&command, &["--mode", "a", "--mode", "b"]), Err(Error::DuplicateOption("--mode".into())));
+    assert_eq!(parse_command(&command, &["--mode", "a", "--mode=b"]), Err(Error::DuplicateOption("--mode".into())));
+    assert_eq!(parse_command(&command, &["--level", "1"]), Err(Error::MissingArgument("--mode".into())));
+    assert_eq!(parse_command(&command, &["--mode", "a", "--other"]), Err(Error::UnknownOption("--other".into())));
+    assert_eq!(parse_command(&command, &["--mode"]), Err(Error::UnexpectedEnd));
+    assert_eq!(parse_command(&command, &["--help"]), Err(Error::HelpRequested));
+    assert_eq!(parse_command(&command, &["-V"]), Err(Error::VersionRequested));
+    assert_eq!(parse_command(&command, &["--mo", "a"]), Err(Error::UnknownOption("--mo".into())));
+
+    let command = command.allow_abbrev();
+    assert_eq!(parse_command(&command, &["--mo", "a", "--lev=2"]).unwrap().integer("level"), Some(2));
+    assert_eq!(parse_command(&command, &["--mo", "--lev"]), Err(Error::MissingValues("--mode".into(), 1, 0)));
+    assert_eq!(parse_command(&command, &["--vers"]), Err(Error::VersionRequested));
+}
+
+#[test]
+pub fn test_command_definition_errors() {
+    let command = Command::new("plugin").arg(OptSpec::flag("verbose").value_type(ValueType::Integer));
+    assert_eq!(command.check_definition(), Err(Error::InvalidDefinition("Flag verbose cannot take values".into())));
+    assert_eq!(parse_command(&command, &[]), Err(Error::InvalidDefinition("Flag verbose cannot take values".into())));
+
+    let command = Command::new("plugin").arg(OptSpec::option("size").num_values(0));
+    assert_eq!(parse_command(&command, &[]), Err(Error::InvalidDefinition("Option size must take at least one value".into())));
+
+    let command = Command::new("plugin").arg(OptSpec::option("mode")).arg(OptSpec::flag("mode"));
+    assert_eq!(parse_command(&command, &[]), Err(Error::InvalidDefinition("Argument mode is defined twice".into())));
+
+    let command = Command::new("plugin").arg(OptSpec::option("mode").short("m")).arg(OptSpec::flag("minimal").short("m"));
+    assert_eq!(parse_command(&command, &["-m", "x"]), Err(Error::InvalidDefinition("Option -m is defined twice".into())));
+    assert_eq!(command.schema().options.len(), 2);
+}
+
+#[test]
+pub fn test_command_stop_at_first_positional() {
+    let command = Command::new("wrapper")
+        .arg(OptSpec::flag("verbose"))
+        .arg(OptSpec::positional("command", 1))
+        .allow_unknown();
+
+    let matches = parse_command(&command.clone().permute(), &["run", "--verbose", "-h"]).unwrap();
+    assert!(matches.flag("verbose"));
+    assert_eq!(matches.unknown_args(), &["-h".to_string()]);
+
+    let matches = parse_command(&command.clone().stop_at_first_positional(), &["run", "--verbose", "--help"]).unwrap();
+    assert!(!matches.flag("verbose"));
+    assert_eq!(matches.string("command"), Some("run"));
+    assert_eq!(matches.unknown_args(), &["--verbose".to_string(), "--help".to_string()]);
+
+    let command = command.arg(OptSpec::positional("arg", 2)).stop_at_first_positional();
+    let matches = parse_command(&command, &["run", "--x"]).unwrap();
+    assert_eq!(matches.string("arg"), Some("--x"));
+}
+
+#[test]
+pub fn test_command_posixly_correct() {
+    // This is the only test that reads the environment variable.
+    let command = Command::new("wrapper")
+        .arg(OptSpec::flag("verbose"))
+        .arg(OptSpec::positional("command", 1))
+        .allow_unknown()
+        .posixly_correct();
+
+    std::env::remove_var("POSIXLY_CORRECT");
+    let matches = parse_command(&command, &["run", "--verbose"]).unwrap();
+    assert!(matches.flag("verbose"));
+
+    std::env::set_var("POSIXLY_CORRECT", "1");
+    let matches = parse_command(&command, &["run", "--verbose"]);
+    std::env::remove_var("POSIXLY_CORRECT");
+    let matches = matches.unwrap();
+    assert!(!matches.flag("verbose"));
+    assert_eq!(matches.unknown_args(), &["--verbose".to_string()]);
+}
+
+#[test]
+pub fn test_command_duplicate_options() {
+    let command = Command::new("plugin")
+        .arg(OptSpec::option("mode").short("m"))
+        .arg(OptSpec::option("level").duplicate_options(DuplicateOptions::FirstWins))
+        .arg(OptSpec::option("name").allow_hyphen_values());
+
+    assert_eq!(parse_command(&command, &["--mode", "a", "-m", "b"]), Err(Error::DuplicateOption("-m".into())));
+    let matches = parse_command(&command, &["--level", "1", "--level", "2", "--name", "--mode"]).unwrap();
+    assert_eq!(matches.string("level"), Some("1"));
+    assert_eq!(matches.string("name"), Some("--mode"));
+
+    let command = command.duplicate_options(DuplicateOptions::LastWins);
+    let matches = parse_command(&command, &["--mode", "a", "-m", "b", "--level", "1", "--level", "2"]).unwrap();
+    assert_eq!(matches.values("mode"), &[Value::String("b".into())]);
+    assert_eq!(matches.string("level"), Some("1"));
+}
+
+#[test]
+pub fn test_command_hyphen_values() {
+    let command = Command::new("plugin")
+        .arg(OptSpec::option("offset").value_type(ValueType::Integer))
+        .arg(OptSpec::option("name"))
+        .arg(OptSpec::option("pattern").allow_hyphen_values())
+        .arg(OptSpec::positional("delta", 1).value_type(ValueType::Float))
+        .allow_unknown();
+
+    let matches = parse_command(&command, &["-1.5", "--offset", "-5", "--pattern", "-x", "--name=-y"]).unwrap();
+    assert_eq!(matches.float("delta"), Some(-1.5));
+    assert_eq!(matches.integer("offset"), Some(-5));
+    assert_eq!(matches.string("pattern"), Some("-x"));
+    assert_eq!(matches.string("name"), Some("-y"));
+    assert_eq!(parse_command(&command, &["--name", "-y"]), Err(Error::MissingValues("--name".into(), 1, 0)));
+    assert_eq!(parse_command(&command, &["--offset", "--name", "x"]), Err(Error::MissingValues("--offset".into(), 1, 0)));
+
+    let matches = parse_command(&command, &["-x"]).unwrap();
+    assert!(!matches.contains("delta"));
+    assert_eq!(matches.unknown_args(), &["-x".to_string()]);
+
+    let command = command.arg(OptSpec::flag("one").short("1"));
+    assert_eq!(parse_command(&command, &["--offset", "-1"]), Err(Error::MissingValues("--offset".into(), 1, 0)));
+}
+
+#[test]
+pub fn test_command_help() {
+    let command = Command::new("plugin")
+        .arg(OptSpec::option("level").short("l").default_value("1").help("Compression level"))
+        .arg(OptSpec::flag("verbose"))
+        .arg(OptSpec::positional("input", 1).required().help("Input file"));
+
+    let schema = command.schema();
+    assert_eq!(schema.usage("prog"), "Usage: plugin [OPTIONS] <INPUT>");
+    assert_eq!(schema.help("prog"), "Usage: plugin [OPTIONS] <INPUT>\n\nArguments:\n  <INPUT>              Input file\n\nOptions:\n  -l, --level <LEVEL>  Compression level [default: 1]\n      --verbose\n  -h, --help           Print help\n");
+}
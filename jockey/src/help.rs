@@ -0,0 +1,48 @@
+use std::path::Path;
+
+/// Render a help text from the usage line and the entries of its sections.
+///
+/// Each entry is a pair of the names of an argument or option and its description. The
+/// descriptions of both sections are aligned to the same column.
+pub fn render_help(usage: &str, arguments: &[(&str, &str)], options: &[(&str, &str)]) -> String {
+    let width = arguments.iter().chain(options.iter()).map(|entry| entry.0.chars().count()).max().unwrap_or(0);
+
+    let mut help = String::from(usage);
+    help.push('\n');
+    for &(title, entries) in [("Arguments", arguments), ("Options", options)].iter() {
+        if entries.is_empty() {
+            continue;
+        }
+        help.push_str(&format!("\n{}:\n", title));
+        for &(names, description) in entries {
+            if description.is_empty() {
+                help.push_str(&format!("  {}\n", names));
+            }
+            else {
+                help.push_str(&format!("  {:width$}  {}\n", names, description, width = width));
+            }
+        }
+    }
+    help
+}
+
+/// The name of the program as called, without its directory.
+pub fn program_name(path: &str) -> String {
+    match Path::new(path).file_name() {
+        Some(name) => name.to_string_lossy().into_owned(),
+        None => path.to_string(),
+    }
+}
+
+#[test]
+pub fn test_render_help() {
+    let help = render_help("Usage: prog [OPTIONS] <FILE>", &[("<FILE>", "Input file")], &[("-v, --verbose", ""), ("-h, --help", "Print help")]);
+    assert_eq!(help, "Usage: prog [OPTIONS] <FILE>\n\nArguments:\n  <FILE>         Input file\n\nOptions:\n  -v, --verbose\n  -h, --help     Print help\n");
+}
+
+#[test]
+pub fn test_program_name() {
+    assert_eq!(program_name("/usr/bin/prog"), "prog");
+    assert_eq!(program_name("prog"), "prog");
+    assert_eq!(program_name(""), "");
+}
@@ -11,17 +11,17 @@
 //! extern crate jockey_derive;
 //!
 //! use jockey::Arguments;
-//! use std::env;
 //!
 //! #[derive(Arguments, Default)]
 //! struct MyArguments {
+//!     /// Shown next to "--my-arg" in the help.
 //!     pub my_arg: Option<String>,
 //!     pub my_flag: bool,
 //! }
 //!
 //! fn main() {
-//!     let args = MyArguments::parse_args(env::args())
-//!         .expect("Error parsing command-line options");
+//!     // Prints the help for "--help" and exits with code 2 on errors.
+//!     let args = MyArguments::parse_or_exit();
 //!
 //!     println!("--my-arg = {:?}", args.my_arg);
 //!     println!("--my-flag = {}", args.my_flag);
@@ -30,8 +30,30 @@
 //!
 //! A more sophisticated example using field attributes can be found in the
 //! [Arguments](trait.Arguments.html) documentation.
+//!
+//! Enums without fields can be used as values by adding `#[derive(Parsable)]`, which matches the
+//! variants by their kebab-case names.
+//!
+//! Parsers whose options are only known at runtime can be built with [Command](struct.Command.html)
+//! instead, which parses into a map of values.
 mod arguments;
-pub use arguments::Arguments;
+pub use arguments::{Arguments, ToArgs};
+
+mod command;
+pub use command::{Command, Matches, OptSpec, Value, ValueType};
+
+mod help;
+pub use help::{program_name, render_help};
+
+mod schema;
+pub use schema::{ArgumentSchema, OptionSchema, Schema};
+
+mod provenance;
+pub use provenance::{FieldMeta, Parsed, Source};
+
+mod matching;
+pub use matching::{canonical_value, expand_abbreviation, is_negative_number, is_option_like, is_value_like, match_ignore_case, posixly_correct, split_glued_value, translate_value};
+pub use matching::{check_builtin_option, check_duplicate_option, check_option_values, rewrite_next_arg};
 
 mod result;
 pub use result::Error;
@@ -40,4 +62,16 @@ pub use result::Result;
 mod parsable;
 pub use parsable::ParsableWithOption;
 pub use parsable::ParsableWithPosition;
+pub use parsable::ParsableValue;
+pub use parsable::FormattableWithOption;
+pub use parsable::FormattableWithPosition;
+pub use parsable::FormattableValue;
+pub use parsable::ParsableMap;
+pub use parsable::DuplicateKeys;
+pub use parsable::DuplicateOptions;
 pub use parsable::ParseResult;
+pub use parsable::{parse_delimited, parse_key_value, parse_option_value, parse_option_values, parse_position_value};
+pub use parsable::{parse_delimited_with, parse_option_with, parse_optional_option_with, parse_position_with, validate_option_with};
+pub use parsable::{parse_default_delimited_with, parse_default_with};
+pub use parsable::{format_option_value, join_escaped};
+pub use parsable::check_duplicate_keys;
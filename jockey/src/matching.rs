@@ -0,0 +1,257 @@
+use parsable::DuplicateOptions;
+use result::{Error, Result};
+use std::collections::HashSet;
+use std::env;
+use std::iter::Peekable;
+
+/// Expand an abbreviated long option like "--verb" to the option it is a unique prefix of.
+///
+/// Only arguments starting with the long option `prefix` are expanded. An attached value is kept,
+/// so "--verb=x" becomes "--verbose=x". Returns `None` if `arg` is not a long option, already
+/// names one of `options` exactly or is a prefix of none. Fails with `Error::AmbiguousOption` if
+/// it is a prefix of several options.
+pub fn expand_abbreviation(arg: &str, prefix: &str, options: &[&str]) -> Result<Option<String>> {
+    if !arg.starts_with(prefix) || arg.len() == prefix.len() {
+        return Ok(None);
+    }
+    let (name, value) = match arg.find('=') {
+        Some(index) => arg.split_at(index),
+        None => (arg, ""),
+    };
+    if options.contains(&name) {
+        return Ok(None);
+    }
+
+    let candidates: Vec<&str> = options.iter().cloned().filter(|option| option.starts_with(name)).collect();
+    match candidates.len() {
+        0 => Ok(None),
+        1 => Ok(Some(format!("{}{}", candidates[0], value))),
+        _ => Err(Error::AmbiguousOption(name.to_string(), candidates.iter().map(|option| option.to_string()).collect())),
+    }
+}
+
+/// Replace a long option given in another case, like "--Verbose", with the matching option.
+///
+/// An attached value is kept. Returns `None` if `arg` does not start with the long option `prefix`
+/// or matches none of `options` when case is ignored.
+pub fn match_ignore_case(arg: &str, prefix: &str, options: &[&str]) -> Option<String> {
+    if !arg.starts_with(prefix) || arg.len() == prefix.len() {
+        return None;
+    }
+    let (name, value) = match arg.find('=') {
+        Some(index) => arg.split_at(index),
+        None => (arg, ""),
+    };
+    let folded = name.to_lowercase();
+    options.iter().find(|option| **option != name && option.to_lowercase() == folded).map(|option| format!("{}{}", option, value))
+}
+
+/// Split a value glued to a long option like "-Xmx1g" into "-Xmx=1g".
+///
+/// The longest of `value_options` that `arg` starts with is taken. Returns `None` if `arg`, or its
+/// part before "=", names one of `options` exactly or starts with none of `value_options`.
+pub fn split_glued_value(arg: &str, options: &[&str], value_options: &[&str]) -> Option<String> {
+    let name = arg.split('=').next().unwrap_or(arg);
+    if options.contains(&name) {
+        return None;
+    }
+    value_options.iter().filter(|option| arg.len() > option.len() && arg.starts_with(**option))
+        .max_by_key(|option| option.len())
+        .map(|option| format!("{}={}", option, &arg[option.len()..]))
+}
+
+/// The possible value that equals `value` when case is ignored, or `value` if there is none.
+pub fn canonical_value<'a>(value: &'a str, possible_values: &[&'a str]) -> &'a str {
+    let folded = value.to_lowercase();
+    possible_values.iter().cloned().find(|possible| possible.to_lowercase() == folded).unwrap_or(value)
+}
+
+/// The element of `to` at the index of `value` in `from`, or `value` if it is not in `from`.
+///
+/// Translates between the possible values of a type and their names following a `rename_all` rule,
+/// see `ParsableValue::renamed_values`.
+pub fn translate_value<'a>(value: &'a str, from: &[&'a str], to: &[&'a str]) -> &'a str {
+    match from.iter().position(|name| *name == value) {
+        Some(index) => to.get(index).cloned().unwrap_or(value),
+        None => value,
+    }
+}
+
+/// Whether an argument looks like an option, i.e. starts with one of the option `prefixes`.
+///
+/// A prefix on its own like "-", which usually stands for stdin, is not an option.
+pub fn is_option_like(arg: &str, prefixes: &[&str]) -> bool {
+    prefixes.iter().any(|prefix| arg.starts_with(prefix) && arg.len() > prefix.len())
+}
+
+/// Whether an argument is a negative number like "-5", "-1.5" or "-1e3".
+pub fn is_negative_number(arg: &str) -> bool {
+    arg.starts_with('-') && arg[1..].starts_with(|c: char| c.is_ascii_digit() || c == '.') && arg.parse::<f64>().is_ok()
+}
+
+/// Whether an argument can be taken as the value of an option or as a positional argument.
+///
+/// Arguments that look like options are not values. With `negative_number` a negative number is a
+/// value anyway, unless it starts with one of the `options` like a short option "-1".
+pub fn is_value_like(arg: &str, prefixes: &[&str], negative_number: bool, options: &[&str]) -> bool {
+    if !is_option_like(arg, prefixes) {
+        return true;
+    }
+    negative_number && is_negative_number(arg) && !options.iter().any(|option| arg.starts_with(option))
+}
+
+/// Replace the next argument on the iterator with what `rewrite` returns for it, if anything.
+///
+/// Used with `expand_abbreviation` and `match_ignore_case` before the argument is matched.
+pub fn rewrite_next_arg<I, F>(iter: &mut Peekable<I>, rewrite: F) -> Result<()>
+    where I: Iterator<Item = (usize, String)>, F: FnOnce(&str) -> Result<Option<String>>
+{
+    let rewritten = match iter.peek() {
+        Some((_, arg)) => rewrite(arg)?,
+        None => None,
+    };
+    if let (Some(rewritten), Some((_, arg))) = (rewritten, iter.peek_mut()) {
+        *arg = rewritten;
+    }
+    Ok(())
+}
+
+/// Fail with `Error::HelpRequested` or `Error::VersionRequested` if `arg` is one of the automatic
+/// help or version options.
+pub fn check_builtin_option(arg: &str, help_options: &[&str], version_options: &[&str]) -> Result<()> {
+    if help_options.contains(&arg) {
+        return Err(Error::HelpRequested);
+    }
+    if version_options.contains(&arg) {
+        return Err(Error::VersionRequested);
+    }
+    Ok(())
+}
+
+/// Fail with `Error::MissingValues` unless the first `count` of `values`, which follow `option`,
+/// can be taken as values. `takes_value` is called with the index and the value, e.g. to check it
+/// with `is_value_like`.
+pub fn check_option_values<F>(option: &str, values: &[String], count: usize, takes_value: F) -> Result<()>
+    where F: Fn(usize, &str) -> bool
+{
+    for (found, value) in values.iter().take(count).enumerate() {
+        if !takes_value(found, value) {
+            return Err(Error::MissingValues(option.to_string(), count, found));
+        }
+    }
+    Ok(())
+}
+
+/// Applies `policy` to a value parsed for the field or option `name`, returning whether the value
+/// is kept.
+///
+/// Only values whose parse result sets `blacklist`, i.e. that may be given once, are tracked in
+/// `given`. Fails with `Error::DuplicateOption` for the "error" policy.
+pub fn check_duplicate_option<'a>(given: &mut HashSet<&'a str>, name: &'a str, blacklist: Option<&String>, policy: DuplicateOptions) -> Result<bool> {
+    let option = match blacklist {
+        Some(option) => option,
+        None => return Ok(true),
+    };
+    if given.insert(name) {
+        return Ok(true);
+    }
+    match policy {
+        DuplicateOptions::Error => Err(Error::DuplicateOption(option.to_string())),
+        DuplicateOptions::FirstWins => Ok(false),
+        DuplicateOptions::LastWins => Ok(true),
+    }
+}
+
+/// Whether the `POSIXLY_CORRECT` environment variable is set, which asks to stop matching options
+/// at the first positional argument.
+pub fn posixly_correct() -> bool {
+    env::var_os("POSIXLY_CORRECT").is_some()
+}
+
+#[test]
+pub fn test_is_option_like() {
+    assert!(is_option_like("--foo", &["--", "-"]));
+    assert!(is_option_like("-f", &["--", "-"]));
+    assert!(!is_option_like("-", &["--", "-"]));
+    assert!(!is_option_like("file", &["--", "-"]));
+    assert!(is_option_like("/f", &["/"]));
+}
+
+#[test]
+pub fn test_is_value_like() {
+    assert!(is_negative_number("-5"));
+    assert!(is_negative_number("-1.5e3"));
+    assert!(is_negative_number("-.5"));
+    assert!(!is_negative_number("-inf"));
+    assert!(!is_negative_number("-5x"));
+    assert!(!is_negative_number("5"));
+
+    let prefixes = ["--", "-"];
+    assert!(is_value_like("file", &prefixes, false, &[]));
+    assert!(is_value_like("-", &prefixes, false, &[]));
+    assert!(!is_value_like("-x", &prefixes, true, &[]));
+    assert!(!is_value_like("-5", &prefixes, false, &[]));
+    assert!(is_value_like("-5", &prefixes, true, &["-v", "--verbose"]));
+    assert!(!is_value_like("-15", &prefixes, true, &["-1"]));
+    assert!(is_value_like("-5", &["+"], false, &[]));
+}
+
+#[test]
+pub fn test_match_ignore_case() {
+    let options = ["--verbose", "--logLevel"];
+    assert_eq!(match_ignore_case("--Verbose", "--", &options), Some("--verbose".into()));
+    assert_eq!(match_ignore_case("--LOGLEVEL=Info", "--", &options), Some("--logLevel=Info".into()));
+    assert_eq!(match_ignore_case("--verbose", "--", &options), None);
+    assert_eq!(match_ignore_case("--other", "--", &options), None);
+    assert_eq!(match_ignore_case("-V", "--", &options), None);
+
+    assert_eq!(canonical_value("INFO", &["debug", "info"]), "info");
+    assert_eq!(canonical_value("Other", &["debug", "info"]), "Other");
+}
+
+#[test]
+pub fn test_expand_abbreviation() {
+    let options = ["--verbose", "--version", "--output"];
+    assert_eq!(expand_abbreviation("--verb", "--", &options), Ok(Some("--verbose".into())));
+    assert_eq!(expand_abbreviation("--o=x", "--", &options), Ok(Some("--output=x".into())));
+    assert_eq!(expand_abbreviation("--output", "--", &options), Ok(None));
+    assert_eq!(expand_abbreviation("--other", "--", &options), Ok(None));
+    assert_eq!(expand_abbreviation("-v", "--", &options), Ok(None));
+    assert_eq!(expand_abbreviation("--", "--", &options), Ok(None));
+    assert_eq!(expand_abbreviation("-verb", "-", &["-v", "-verbose"]), Ok(Some("-verbose".into())));
+    assert_eq!(expand_abbreviation("-v", "-", &["-v", "-verbose"]), Ok(None));
+    assert_eq!(expand_abbreviation("--ver", "--", &options), Err(Error::AmbiguousOption("--ver".into(), vec!["--verbose".into(), "--version".into()])));
+}
+
+#[test]
+pub fn test_split_glued_value() {
+    let options = ["-Xmx", "-X", "-D", "-v"];
+    let value_options = ["-Xmx", "-X", "-D"];
+    assert_eq!(split_glued_value("-Xmx1g", &options, &value_options), Some("-Xmx=1g".into()));
+    assert_eq!(split_glued_value("-Xss1m", &options, &value_options), Some("-X=ss1m".into()));
+    assert_eq!(split_glued_value("-Dkey=value", &options, &value_options), Some("-D=key=value".into()));
+    assert_eq!(split_glued_value("-Xmx", &options, &value_options), None);
+    assert_eq!(split_glued_value("-Xmx=1g", &options, &value_options), None);
+    assert_eq!(split_glued_value("-v", &options, &value_options), None);
+    assert_eq!(split_glued_value("-verbose", &options, &value_options), None);
+}
+
+#[test]
+pub fn test_translate_value() {
+    let names = ["very-fast", "slow"];
+    let renamed = ["veryFast", "slow"];
+    assert_eq!(translate_value("veryFast", &renamed, &names), "very-fast");
+    assert_eq!(translate_value("very-fast", &names, &renamed), "veryFast");
+    assert_eq!(translate_value("other", &renamed, &names), "other");
+}
+
+#[test]
+pub fn test_check_duplicate_option() {
+    let mut given = HashSet::new();
+    let option = "--foo".to_string();
+    assert_eq!(check_duplicate_option(&mut given, "foo", Some(&option), DuplicateOptions::Error), Ok(true));
+    assert_eq!(check_duplicate_option(&mut given, "foo", Some(&option), DuplicateOptions::Error), Err(Error::DuplicateOption("--foo".into())));
+    assert_eq!(check_duplicate_option(&mut given, "foo", Some(&option), DuplicateOptions::FirstWins), Ok(false));
+    assert_eq!(check_duplicate_option(&mut given, "foo", Some(&option), DuplicateOptions::LastWins), Ok(true));
+    assert_eq!(check_duplicate_option(&mut given, "foo", None, DuplicateOptions::Error), Ok(true));
+}
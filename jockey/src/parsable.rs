@@ -1,5 +1,13 @@
 use result::{Result, Error};
+use std::collections::{BTreeMap, HashMap};
+use std::convert::TryFrom;
+use std::fmt::Display;
+use std::hash::Hash;
 use std::iter::Peekable;
+use std::num::{NonZeroI8, NonZeroI16, NonZeroI32, NonZeroI64, NonZeroI128, NonZeroIsize};
+use std::num::{NonZeroU8, NonZeroU16, NonZeroU32, NonZeroU64, NonZeroU128, NonZeroUsize};
+use std::path::PathBuf;
+use std::str::FromStr;
 
 /// Result object for Parsable::parse_arg.
 #[derive(Debug, Clone)]
@@ -8,7 +16,8 @@ pub struct ParseResult<T> {
     /// Parse result.
     pub parsed: Option<Result<T>>,
 
-    /// An option string that should not be accepted anymore.
+    /// The option that was matched if it may only be given once, see `DuplicateOptions`. `None`
+    /// for types that collect several occurrences like `Vec`.
     pub blacklist: Option<String>,
 }
 
@@ -16,8 +25,8 @@ impl<T> ParseResult<T> {
     /// Create a new ParseResult object.
     pub fn new(parsed: Option<Result<T>>, blacklist: Option<String>) -> Self {
         ParseResult {
-            parsed: parsed,
-            blacklist: blacklist,
+            parsed,
+            blacklist,
         }
     }
 
@@ -35,12 +44,18 @@ impl<T> ParseResult<T> {
     pub fn err(err: Error) -> Self {
         ParseResult::new(Some(Err(err)), None)
     }
+
+    /// Convert the parsed value, keeping errors and the blacklist.
+    pub fn map<U, F: FnOnce(T) -> U>(self, convert: F) -> ParseResult<U> {
+        ParseResult::new(self.parsed.map(|parsed| parsed.map(convert)), self.blacklist)
+    }
 }
 
 /// Implemented for types parsable with an option like "--foo" in Arguments::parse_args().
 pub trait ParsableWithOption : Sized {
 
     /// Parse the next argument on the iterator if possible.
+    #[allow(clippy::ptr_arg)]
     fn parse_arg<I>(iter: &mut Peekable<I>, option: &String) -> ParseResult<Self>
         where I: Iterator<Item = (usize, String)>;
 
@@ -49,7 +64,18 @@ pub trait ParsableWithOption : Sized {
     /// This needs to be overriden for types with multiplicity (see implementation of Parsable for
     /// Vec<String>). For types without multiplicity the default implementation will do just fine.
     fn assign(_lhs: Self, rhs: Self) -> Self {
-        return rhs;
+        rhs
+    }
+
+    /// The values that are accepted, if there is a fixed set of them, like
+    /// `ParsableValue::possible_values`. They are listed in `Arguments::schema`.
+    fn possible_values() -> &'static [&'static str] {
+        &[]
+    }
+
+    /// Other names that are accepted for some of the `possible_values`.
+    fn aliases() -> &'static [&'static str] {
+        &[]
     }
 }
 
@@ -59,45 +85,499 @@ pub trait ParsableWithPosition : Sized {
     /// Parse the next argument on the iterator if possible.
     fn parse_arg<I>(iter: &mut Peekable<I>, position: usize) -> ParseResult<Self>
         where I: Iterator<Item = (usize, String)>;
+
+    /// The values that are accepted, if there is a fixed set of them, see
+    /// `ParsableWithOption::possible_values`.
+    fn possible_values() -> &'static [&'static str] {
+        &[]
+    }
+
+    /// Other names that are accepted for some of the `possible_values`.
+    fn aliases() -> &'static [&'static str] {
+        &[]
+    }
+}
+
+/// Implemented for types that can be converted from a single command-line value.
+///
+/// This is what allows a type to be an element of a delimited list (see `parse_delimited`). The
+/// error is a human-readable reason which ends up in `Error::InvalidValue`.
+pub trait ParsableValue : Sized {
+
+    /// Convert a single value.
+    fn parse_value(value: &str) -> std::result::Result<Self, String>;
+
+    /// The values that are accepted, if there is a fixed set of them.
+    fn possible_values() -> &'static [&'static str] {
+        &[]
+    }
+
+    /// Other names that are accepted for some of the `possible_values`.
+    fn aliases() -> &'static [&'static str] {
+        &[]
+    }
+
+    /// The `possible_values` in the same order, named following the `rename_all` rule of a struct
+    /// like "snake_case". The default implementation returns `possible_values`.
+    fn renamed_values(_rule: &str) -> &'static [&'static str] {
+        Self::possible_values()
+    }
+}
+
+/// Implemented for types that can be formatted back into arguments after an option like "--foo",
+/// see `ToArgs::to_args`.
+pub trait FormattableWithOption {
+
+    /// Formats the value back into arguments that `ParsableWithOption::parse_arg` turns into an
+    /// equal value.
+    ///
+    /// Values are attached like "--foo=bar" so they are never mistaken for options. The result
+    /// may be empty, e.g. for `false` or `None`.
+    fn format_arg(&self, option: &str) -> Vec<String>;
+
+    /// Formats the value of an occurrence that was given, like an element of `Option` or `Vec`.
+    ///
+    /// Only needs to be overridden by types for which `format_arg` may be empty, like `bool`.
+    fn format_given_arg(&self, option: &str) -> Vec<String> {
+        self.format_arg(option)
+    }
+}
+
+/// Implemented for types that can be formatted back into a positional argument.
+pub trait FormattableWithPosition {
+
+    /// Formats the value back into the argument it was parsed from, if there is one.
+    fn format_arg(&self) -> Option<String>;
+}
+
+/// Implemented for types that can be formatted back into a single command-line value.
+pub trait FormattableValue {
+
+    /// Formats the value back into a string that `ParsableValue::parse_value` accepts.
+    fn format_value(&self) -> String;
+
+    /// Formats the value into one string per occurrence.
+    ///
+    /// Only needs to be overridden by types that collect several occurrences, like `Vec`.
+    fn format_values(&self) -> Vec<String> {
+        vec![self.format_value()]
+    }
+}
+
+/// Parse the value of an option and convert it with `ParsableValue`.
+pub fn parse_option_value<T, I>(iter: &mut Peekable<I>, option: &str) -> ParseResult<T>
+    where T: ParsableValue, I: Iterator<Item = (usize, String)>
+{
+    parse_option_with(iter, option, T::parse_value)
+}
+
+/// Parse the value of an option and convert it with a custom function.
+///
+/// A conversion error is reported as `Error::InvalidValue` along with the option and the value.
+pub fn parse_option_with<T, E, F, I>(iter: &mut Peekable<I>, option: &str, convert: F) -> ParseResult<T>
+    where E: Display, F: FnOnce(&str) -> std::result::Result<T, E>, I: Iterator<Item = (usize, String)>
+{
+    let result = parse_string_arg(iter, option);
+    match result.parsed {
+        Some(Ok(value)) => match convert(&value) {
+            Ok(val) => ParseResult::success(val, result.blacklist),
+            Err(reason) => ParseResult::err(Error::InvalidValue(option.to_string(), value, reason.to_string())),
+        },
+        Some(Err(err)) => ParseResult::err(err),
+        None => ParseResult::none(),
+    }
+}
+
+/// Parse an option whose value is optional and must be attached like "--foo=bar".
+///
+/// A following argument is never taken as the value. `convert` receives `None` if the option is
+/// given without a value.
+pub fn parse_optional_option_with<T, E, F, I>(iter: &mut Peekable<I>, option: &str, convert: F) -> ParseResult<T>
+    where E: Display, F: FnOnce(Option<&str>) -> std::result::Result<T, E>, I: Iterator<Item = (usize, String)>
+{
+    match iter.peek().cloned() {
+        Some((_, arg)) => {
+            let value = if arg == option {
+                None
+            }
+            else if arg.starts_with(option) && arg[option.len()..].starts_with('=') {
+                Some(arg[option.len() + 1..].to_string())
+            }
+            else {
+                return ParseResult::none();
+            };
+            iter.next();
+
+            match convert(value.as_deref()) {
+                Ok(val) => ParseResult::success(val, Some(option.to_string())),
+                Err(reason) => ParseResult::err(Error::InvalidValue(option.to_string(), value.unwrap_or_default(), reason.to_string())),
+            }
+        },
+        None => ParseResult::none(),
+    }
+}
+
+#[test]
+pub fn test_parse_optional_option() {
+    let args_vec = ["--color", "file", "--color=never"];
+    let mut args = args_vec.iter()
+        .map(|x| x.to_string())
+        .enumerate()
+        .peekable();
+
+    let convert = |value: Option<&str>| -> std::result::Result<String, String> { Ok(value.unwrap_or("always").to_string()) };
+
+    let result = parse_optional_option_with(&mut args, "--color", convert);
+    assert_eq!(result.parsed, Some(Ok("always".into())));
+    assert_eq!(result.blacklist, Some("--color".into()));
+
+    let result = parse_optional_option_with(&mut args, "--color", convert);
+    assert!(result.parsed.is_none());
+    assert_eq!(args.next(), Some((1, "file".into())));
+
+    let result = parse_optional_option_with(&mut args, "--color", convert);
+    assert_eq!(result.parsed, Some(Ok("never".into())));
+}
+
+/// Check the value of an option that was parsed by its own `ParsableWithOption` implementation.
+///
+/// This is how `validate_with` works for tuples, arrays and maps. A rejected value is reported as
+/// `Error::InvalidValue` along with the option and the values as `format_arg` writes them.
+pub fn validate_option_with<T, E, F>(result: ParseResult<T>, option: &str, validate: F) -> ParseResult<T>
+    where T: FormattableWithOption, E: Display, F: FnOnce(&T) -> std::result::Result<(), E>
+{
+    match result.parsed {
+        Some(Ok(value)) => match validate(&value) {
+            Ok(()) => ParseResult::success(value, result.blacklist),
+            Err(reason) => {
+                let prefix = format!("{}=", option);
+                let values: Vec<String> = value.format_arg(option).into_iter()
+                    .map(|arg| arg.strip_prefix(prefix.as_str()).map(String::from).unwrap_or(arg))
+                    .collect();
+                ParseResult::err(Error::InvalidValue(option.to_string(), values.join(" "), reason.to_string()))
+            },
+        },
+        parsed => ParseResult::new(parsed, result.blacklist),
+    }
+}
+
+#[test]
+pub fn test_validate_option_with() {
+    let args_vec = ["--resize", "800", "600", "--resize", "1", "2"];
+    let mut args = args_vec.iter()
+        .map(|x| x.to_string())
+        .enumerate()
+        .peekable();
+
+    let validate = |size: &(u32, u32)| if size.0 >= size.1 { Ok(()) } else { Err("must be landscape") };
+
+    let result = validate_option_with(<(u32, u32) as ParsableWithOption>::parse_arg(&mut args, &"--resize".to_string()), "--resize", validate);
+    assert_eq!(result.parsed, Some(Ok((800, 600))));
+    assert_eq!(result.blacklist, Some("--resize".into()));
+
+    let result = validate_option_with(<(u32, u32) as ParsableWithOption>::parse_arg(&mut args, &"--resize".to_string()), "--resize", validate);
+    assert_eq!(result.parsed, Some(Err(Error::InvalidValue("--resize".into(), "1 2".into(), "must be landscape".into()))));
+}
+
+/// Parse the argument at a position and convert it with `ParsableValue`.
+pub fn parse_position_value<T, I>(iter: &mut Peekable<I>, position: usize) -> ParseResult<T>
+    where T: ParsableValue, I: Iterator<Item = (usize, String)>
+{
+    parse_position_with(iter, position, T::parse_value)
+}
+
+/// Parse the argument at a position and convert it with a custom function.
+pub fn parse_position_with<T, E, F, I>(iter: &mut Peekable<I>, position: usize, convert: F) -> ParseResult<T>
+    where E: Display, F: FnOnce(&str) -> std::result::Result<T, E>, I: Iterator<Item = (usize, String)>
+{
+    let result = <String as ParsableWithPosition>::parse_arg(iter, position);
+    match result.parsed {
+        Some(Ok(value)) => match convert(&value) {
+            Ok(val) => ParseResult::success(val, result.blacklist),
+            Err(reason) => ParseResult::err(Error::InvalidValue(format!("argument {}", position), value, reason.to_string())),
+        },
+        Some(Err(err)) => ParseResult::err(err),
+        None => ParseResult::none(),
+    }
+}
+
+/// Parse an option value holding several elements separated by `delimiter`.
+///
+/// A backslash escapes the delimiter (and itself). Each element is converted on its own so an
+/// error names the offending element rather than the whole value. An empty value yields one
+/// empty element.
+pub fn parse_delimited<T, I>(iter: &mut Peekable<I>, option: &str, delimiter: char) -> ParseResult<Vec<T>>
+    where T: ParsableValue, I: Iterator<Item = (usize, String)>
+{
+    parse_delimited_with(iter, option, delimiter, T::parse_value)
+}
+
+/// Like `parse_delimited` but converts the elements with a custom function.
+pub fn parse_delimited_with<T, E, F, I>(iter: &mut Peekable<I>, option: &str, delimiter: char, mut convert: F) -> ParseResult<Vec<T>>
+    where E: Display, F: FnMut(&str) -> std::result::Result<T, E>, I: Iterator<Item = (usize, String)>
+{
+    let result = parse_string_arg(iter, option);
+    match result.parsed {
+        Some(Ok(value)) => {
+            let mut elements = Vec::new();
+            for piece in split_escaped(&value, delimiter) {
+                match convert(&piece) {
+                    Ok(val) => elements.push(val),
+                    Err(reason) => return ParseResult::err(Error::InvalidValue(option.to_string(), piece, reason.to_string())),
+                }
+            }
+            ParseResult::success(elements, None)
+        },
+        Some(Err(err)) => ParseResult::err(err),
+        None => ParseResult::none(),
+    }
+}
+
+/// Convert the default value of an option or positional argument that was not given.
+///
+/// A conversion error is reported as `Error::InvalidValue` along with `name` and the value.
+pub fn parse_default_with<T, E, F>(name: &str, value: &str, convert: F) -> Result<T>
+    where E: Display, F: FnOnce(&str) -> std::result::Result<T, E>
+{
+    convert(value).map_err(|reason| Error::InvalidValue(name.to_string(), value.to_string(), reason.to_string()))
+}
+
+/// Like `parse_default_with` but splits the value like `parse_delimited` does.
+pub fn parse_default_delimited_with<T, E, F>(name: &str, value: &str, delimiter: char, mut convert: F) -> Result<Vec<T>>
+    where E: Display, F: FnMut(&str) -> std::result::Result<T, E>
+{
+    split_escaped(value, delimiter).iter().map(|piece| parse_default_with(name, piece, &mut convert)).collect()
+}
+
+/// Split `value` at every unescaped `delimiter` and remove the escapes.
+fn split_escaped(value: &str, delimiter: char) -> Vec<String> {
+    let mut pieces = Vec::new();
+    let mut current = String::new();
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            match chars.next() {
+                Some(next) if next == delimiter || next == '\\' => current.push(next),
+                Some(next) => { current.push(c); current.push(next); },
+                None => current.push(c),
+            }
+        }
+        else if c == delimiter {
+            pieces.push(current);
+            current = String::new();
+        }
+        else {
+            current.push(c);
+        }
+    }
+    pieces.push(current);
+    pieces
+}
+
+/// Join `pieces` with `delimiter`, escaping it (and backslashes) so `parse_delimited` restores
+/// the pieces.
+pub fn join_escaped<S: AsRef<str>>(pieces: &[S], delimiter: char) -> String {
+    let mut value = String::new();
+    for (index, piece) in pieces.iter().enumerate() {
+        if index > 0 {
+            value.push(delimiter);
+        }
+        for c in piece.as_ref().chars() {
+            if c == delimiter || c == '\\' {
+                value.push('\\');
+            }
+            value.push(c);
+        }
+    }
+    value
+}
+
+#[test]
+pub fn test_join_escaped() {
+    assert_eq!(join_escaped(&["a", "b", "c"], ','), "a,b,c");
+    assert_eq!(join_escaped(&["a,b", "c\\"], ','), "a\\,b,c\\\\");
+    assert_eq!(split_escaped(&join_escaped(&["a\\,", "\\n"], ','), ','), vec!["a\\,", "\\n"]);
+}
+
+#[test]
+pub fn test_split_escaped() {
+    assert_eq!(split_escaped("a,b,c", ','), vec!["a", "b", "c"]);
+    assert_eq!(split_escaped("a\\,b,c", ','), vec!["a,b", "c"]);
+    assert_eq!(split_escaped("a\\\\,b", ','), vec!["a\\", "b"]);
+    assert_eq!(split_escaped("a\\nb", ','), vec!["a\\nb"]);
+    assert_eq!(split_escaped("a,,b", ','), vec!["a", "", "b"]);
+    assert_eq!(split_escaped("", ','), vec![""]);
+}
+
+#[test]
+pub fn test_parse_delimited() {
+    let args_vec = ["--foo", "1,2,3", "--foo", "4,x,6"];
+    let mut args = args_vec.iter()
+        .map(|x| x.to_string())
+        .enumerate()
+        .peekable();
+
+    let result = parse_delimited::<u32, _>(&mut args, "--foo", ',');
+    assert_eq!(result.parsed, Some(Ok(vec![1, 2, 3])));
+    assert_eq!(result.blacklist, None);
+
+    let result = parse_delimited::<u32, _>(&mut args, "--foo", ',');
+    assert_eq!(result.parsed, Some(Err(Error::InvalidValue("--foo".into(), "x".into(), "invalid digit found in string".into()))));
+}
+
+/// Format an option with its value attached like "--foo=bar".
+pub fn format_option_value<T: FormattableValue>(option: &str, value: &T) -> Vec<String> {
+    vec![format!("{}={}", option, value.format_value())]
+}
+
+impl ParsableValue for String {
+    fn parse_value(value: &str) -> std::result::Result<Self, String> {
+        Ok(value.to_string())
+    }
+}
+
+impl FormattableValue for String {
+    fn format_value(&self) -> String {
+        self.clone()
+    }
+}
+
+impl ParsableValue for PathBuf {
+    fn parse_value(value: &str) -> std::result::Result<Self, String> {
+        Ok(PathBuf::from(value))
+    }
+}
+
+impl FormattableValue for PathBuf {
+    fn format_value(&self) -> String {
+        self.to_string_lossy().into_owned()
+    }
+}
+
+macro_rules! impl_parsable_from_str {
+    ($($ty:ty),*) => {$(
+        impl ParsableValue for $ty {
+            fn parse_value(value: &str) -> std::result::Result<Self, String> {
+                <$ty as FromStr>::from_str(value).map_err(|err| err.to_string())
+            }
+        }
+
+        impl FormattableValue for $ty {
+            fn format_value(&self) -> String {
+                self.to_string()
+            }
+        }
+    )*}
+}
+
+macro_rules! impl_parsable_with_value {
+    ($($ty:ty),*) => {$(
+        impl ParsableWithOption for $ty {
+            fn parse_arg<I>(iter: &mut Peekable<I>, option: &String) -> ParseResult<Self>
+                where I: Iterator<Item = (usize, String)>
+            {
+                parse_option_value(iter, option)
+            }
+        }
+
+        impl FormattableWithOption for $ty {
+            fn format_arg(&self, option: &str) -> Vec<String> {
+                format_option_value(option, self)
+            }
+        }
+
+        impl ParsableWithPosition for $ty {
+            fn parse_arg<I>(iter: &mut Peekable<I>, position: usize) -> ParseResult<Self>
+                where I: Iterator<Item = (usize, String)>
+            {
+                parse_position_value(iter, position)
+            }
+        }
+
+        impl FormattableWithPosition for $ty {
+            fn format_arg(&self) -> Option<String> {
+                Some(self.format_value())
+            }
+        }
+    )*}
+}
+
+impl_parsable_from_str!(i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize, f32, f64, char);
+impl_parsable_from_str!(NonZeroI8, NonZeroI16, NonZeroI32, NonZeroI64, NonZeroI128, NonZeroIsize);
+impl_parsable_from_str!(NonZeroU8, NonZeroU16, NonZeroU32, NonZeroU64, NonZeroU128, NonZeroUsize);
+impl_parsable_with_value!(i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize, f32, f64, char, PathBuf);
+impl_parsable_with_value!(NonZeroI8, NonZeroI16, NonZeroI32, NonZeroI64, NonZeroI128, NonZeroIsize);
+impl_parsable_with_value!(NonZeroU8, NonZeroU16, NonZeroU32, NonZeroU64, NonZeroU128, NonZeroUsize);
+
+#[test]
+pub fn test_parsable_for_number() {
+    let args_vec = ["--foo", "42", "--foo=-1", "--bar", "x"];
+    let mut args = args_vec.iter()
+        .map(|x| x.to_string())
+        .enumerate()
+        .peekable();
+
+    let result = <u32 as ParsableWithOption>::parse_arg(&mut args, &"--foo".to_string());
+    assert_eq!(result.parsed, Some(Ok(42)));
+    assert_eq!(result.blacklist, Some("--foo".into()));
+
+    let result = <i64 as ParsableWithOption>::parse_arg(&mut args, &"--foo".to_string());
+    assert_eq!(result.parsed, Some(Ok(-1)));
+
+    let result = <f64 as ParsableWithOption>::parse_arg(&mut args, &"--bar".to_string());
+    assert_eq!(result.parsed, Some(Err(Error::InvalidValue("--bar".into(), "x".into(), "invalid float literal".into()))));
 }
 
 impl ParsableWithOption for String {
     fn parse_arg<I>(iter: &mut Peekable<I>, option: &String) -> ParseResult<Self>
         where I: Iterator<Item = (usize, String)>
     {
-        match iter.peek().cloned() {
-            Some((_, val)) => {
-                // Split arguments of the form "--foo=bar" to "--foo" and "bar"
-                let split: Vec<&str> = val.splitn(2, "=").collect();
-                if split[0] == option {
-                    // Advance the iterator
-                    iter.next();
-
-                    let value: Option<String> = if split.len() == 2 {
-                        Some(split[1].to_string())
-                    }
-                    else {
-                        iter.next().map(|x| x.1)
-                    };
+        parse_string_arg(iter, option)
+    }
+}
 
-                    match value {
-                        Some(value) => ParseResult::success(value, Some(option.clone())),
-                        None => ParseResult::err(Error::UnexpectedEnd),
-                    }
+impl FormattableWithOption for String {
+    fn format_arg(&self, option: &str) -> Vec<String> {
+        format_option_value(option, self)
+    }
+}
+
+/// Parse the value of an option as it is, like `String` does.
+fn parse_string_arg<I>(iter: &mut Peekable<I>, option: &str) -> ParseResult<String>
+    where I: Iterator<Item = (usize, String)>
+{
+    match iter.peek().cloned() {
+        Some((_, val)) => {
+            // Split arguments of the form "--foo=bar" to "--foo" and "bar"
+            let split: Vec<&str> = val.splitn(2, "=").collect();
+            if split[0] == option {
+                // Advance the iterator
+                iter.next();
+
+                let value: Option<String> = if split.len() == 2 {
+                    Some(split[1].to_string())
                 }
                 else {
-                    // Option didn't match
-                    ParseResult::none()
+                    iter.next().map(|x| x.1)
+                };
+
+                match value {
+                    Some(value) => ParseResult::success(value, Some(option.to_string())),
+                    None => ParseResult::err(Error::UnexpectedEnd),
                 }
             }
-            None => ParseResult::none(),
+            else {
+                // Option didn't match
+                ParseResult::none()
+            }
         }
+        None => ParseResult::none(),
     }
 }
 
 #[test]
 pub fn test_parsable_for_string() {
-    let args_vec = vec!["--foo", "bar"];
+    let args_vec = ["--foo", "bar"];
     let mut args = args_vec.iter()
         .map(|x| x.to_string())
         .enumerate()
@@ -108,18 +588,28 @@ pub fn test_parsable_for_string() {
     assert_eq!(result.blacklist, Some("--foo".into()));
 }
 
+/// A flag is true if given like "--foo". An attached value like "--foo=false" sets it explicitly.
 impl ParsableWithOption for bool {
     fn parse_arg<I>(iter: &mut Peekable<I>, option: &String) -> ParseResult<Self>
         where I: Iterator<Item = (usize, String)>
     {
         match iter.peek().cloned() {
             Some((_, key)) => {
-                if key == option.as_ref() {
-                    iter.next();
-                    ParseResult::success(true, Some(option.clone()))
+                let value = if key == *option {
+                    None
+                }
+                else if key.starts_with(option.as_str()) && key[option.len()..].starts_with('=') {
+                    Some(key[option.len() + 1..].to_string())
                 }
                 else {
-                    ParseResult::none()
+                    return ParseResult::none();
+                };
+                iter.next();
+
+                match value.as_deref() {
+                    None | Some("true") => ParseResult::success(true, Some(option.to_string())),
+                    Some("false") => ParseResult::success(false, Some(option.to_string())),
+                    Some(_) => ParseResult::err(Error::InvalidValue(option.to_string(), value.unwrap(), "expected true or false".into())),
                 }
             },
             None => ParseResult::none(),
@@ -127,9 +617,20 @@ impl ParsableWithOption for bool {
     }
 }
 
+impl FormattableWithOption for bool {
+    fn format_arg(&self, option: &str) -> Vec<String> {
+        if *self { vec![option.to_string()] } else { vec![] }
+    }
+
+    /// `false` is given as "--foo=false" so it is not mistaken for a missing value.
+    fn format_given_arg(&self, option: &str) -> Vec<String> {
+        if *self { vec![option.to_string()] } else { vec![format!("{}=false", option)] }
+    }
+}
+
 #[test]
 pub fn test_parsable_for_bool() {
-    let args_vec = vec!["--foo"];
+    let args_vec = ["--foo", "--foo=false", "--foo=no"];
     let mut args = args_vec.iter()
         .map(|x| x.to_string())
         .enumerate()
@@ -138,6 +639,12 @@ pub fn test_parsable_for_bool() {
     let result = <bool as ParsableWithOption>::parse_arg(&mut args, &"--foo".to_string());
     assert_eq!(result.parsed, Some(Ok(true)));
     assert_eq!(result.blacklist, Some("--foo".into()));
+
+    let result = <bool as ParsableWithOption>::parse_arg(&mut args, &"--foo".to_string());
+    assert_eq!(result.parsed, Some(Ok(false)));
+
+    let result = <bool as ParsableWithOption>::parse_arg(&mut args, &"--foo".to_string());
+    assert_eq!(result.parsed, Some(Err(Error::InvalidValue("--foo".into(), "no".into(), "expected true or false".into()))));
 }
 
 impl<T : ParsableWithOption> ParsableWithOption for Option<T> {
@@ -151,11 +658,28 @@ impl<T : ParsableWithOption> ParsableWithOption for Option<T> {
             None => ParseResult::none(),
         }
     }
+
+    fn possible_values() -> &'static [&'static str] {
+        <T as ParsableWithOption>::possible_values()
+    }
+
+    fn aliases() -> &'static [&'static str] {
+        <T as ParsableWithOption>::aliases()
+    }
+}
+
+impl<T : FormattableWithOption> FormattableWithOption for Option<T> {
+    fn format_arg(&self, option: &str) -> Vec<String> {
+        match self {
+            Some(val) => val.format_given_arg(option),
+            None => vec![],
+        }
+    }
 }
 
 #[test]
 pub fn test_parsable_for_option() {
-    let args_vec = vec!["--foo", "bar"];
+    let args_vec = ["--foo", "bar"];
     let mut args = args_vec.iter()
         .map(|x| x.to_string())
         .enumerate()
@@ -180,13 +704,44 @@ impl<T : ParsableWithOption> ParsableWithOption for Vec<T> {
 
     fn assign(mut lhs: Self, mut rhs: Self) -> Self {
         lhs.append(&mut rhs);
-        return lhs;
+        lhs
+    }
+
+    fn possible_values() -> &'static [&'static str] {
+        <T as ParsableWithOption>::possible_values()
+    }
+
+    fn aliases() -> &'static [&'static str] {
+        <T as ParsableWithOption>::aliases()
+    }
+}
+
+impl<T : FormattableWithOption> FormattableWithOption for Vec<T> {
+    fn format_arg(&self, option: &str) -> Vec<String> {
+        self.iter().flat_map(|val| val.format_given_arg(option)).collect()
+    }
+}
+
+impl<T : ParsableValue> ParsableValue for Vec<T> {
+    fn parse_value(value: &str) -> std::result::Result<Self, String> {
+        T::parse_value(value).map(|val| vec![val])
+    }
+}
+
+impl<T : FormattableValue> FormattableValue for Vec<T> {
+    /// Only meaningful for a single element, use `format_values` to get every element.
+    fn format_value(&self) -> String {
+        self.iter().map(|val| val.format_value()).collect::<Vec<_>>().join(" ")
+    }
+
+    fn format_values(&self) -> Vec<String> {
+        self.iter().map(|val| val.format_value()).collect()
     }
 }
 
 #[test]
 pub fn test_parsable_for_vec() {
-    let args_vec = vec!["--foo", "bar", "--foo", "baz"];
+    let args_vec = ["--foo", "bar", "--foo", "baz"];
     let mut args = args_vec.iter()
         .map(|x| x.to_string())
         .enumerate()
@@ -202,17 +757,403 @@ pub fn test_parsable_for_vec() {
     assert_eq!(tmp2.blacklist, None);
 }
 
-impl ParsableWithPosition for String {
-    fn parse_arg<I>(iter: &mut Peekable<I>, position: usize) -> ParseResult<Self>
+/// Parse an option value of the form "KEY=VALUE" and convert both halves with `ParsableValue`.
+pub fn parse_key_value<K, V, I>(iter: &mut Peekable<I>, option: &str) -> ParseResult<(K, V)>
+    where K: ParsableValue, V: ParsableValue, I: Iterator<Item = (usize, String)>
+{
+    let result = parse_string_arg(iter, option);
+    match result.parsed {
+        Some(Ok(entry)) => {
+            let split: Vec<&str> = entry.splitn(2, '=').collect();
+            if split.len() != 2 {
+                return ParseResult::err(Error::InvalidValue(option.to_string(), entry.clone(), "expected KEY=VALUE".into()));
+            }
+            let key = match K::parse_value(split[0]) {
+                Ok(key) => key,
+                Err(reason) => return ParseResult::err(Error::InvalidValue(option.to_string(), split[0].to_string(), reason)),
+            };
+            let value = match V::parse_value(split[1]) {
+                Ok(value) => value,
+                Err(reason) => return ParseResult::err(Error::InvalidValue(option.to_string(), split[1].to_string(), reason)),
+            };
+            ParseResult::success((key, value), None)
+        },
+        Some(Err(err)) => ParseResult::err(err),
+        None => ParseResult::none(),
+    }
+}
+
+/// What to do when an option that may only be given once is given again, also under another of
+/// its names.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DuplicateOptions {
+    /// Fail with `Error::DuplicateOption`.
+    Error,
+
+    /// Keep the value given first.
+    FirstWins,
+
+    /// Keep the value given last.
+    LastWins,
+}
+
+/// What to do when a map option receives a key it already holds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DuplicateKeys {
+    /// Fail with `Error::DuplicateKey`.
+    Error,
+
+    /// Keep the value given last.
+    LastWins,
+
+    /// Combine the values with `ParsableWithOption::assign`, which collects them for `Vec` values.
+    Collect,
+}
+
+/// Implemented for map types that are parsable with options like "--set KEY=VALUE".
+pub trait ParsableMap : ParsableWithOption {
+
+    /// Merges the entries of the right hand side into the left hand side following `policy`.
+    fn merge(lhs: Self, rhs: Self, policy: DuplicateKeys, option: &str) -> Result<Self>;
+}
+
+impl<K, V> ParsableWithOption for HashMap<K, V>
+    where K: ParsableValue + Eq + Hash, V: ParsableValue
+{
+    fn parse_arg<I>(iter: &mut Peekable<I>, option: &String) -> ParseResult<Self>
         where I: Iterator<Item = (usize, String)>
     {
-        match iter.peek().cloned() {
-            Some((pos, ref val)) => {
-                println!("TEST: {}, {}", pos, val);
+        let result = parse_key_value(iter, option);
+        match result.parsed {
+            Some(Ok((key, value))) => {
+                let mut map = HashMap::new();
+                map.insert(key, value);
+                ParseResult::success(map, None)
+            },
+            Some(Err(err)) => ParseResult::err(err),
+            None => ParseResult::none(),
+        }
+    }
+
+    fn assign(mut lhs: Self, rhs: Self) -> Self {
+        lhs.extend(rhs);
+        lhs
+    }
+}
+
+impl<K, V> FormattableWithOption for HashMap<K, V>
+    where K: FormattableValue, V: FormattableValue
+{
+    /// Entries are sorted so the arguments do not depend on the iteration order of the map.
+    fn format_arg(&self, option: &str) -> Vec<String> {
+        let mut args = format_entries(self.iter(), option);
+        args.sort();
+        args
+    }
+}
+
+impl<K, V> ParsableMap for HashMap<K, V>
+    where K: ParsableValue + Eq + Hash + Display, V: ParsableValue + ParsableWithOption
+{
+    fn merge(mut lhs: Self, rhs: Self, policy: DuplicateKeys, option: &str) -> Result<Self> {
+        for (key, value) in rhs {
+            let value = match lhs.remove(&key) {
+                Some(old) => merge_value(&key, old, value, policy, option)?,
+                None => value,
+            };
+            lhs.insert(key, value);
+        }
+        Ok(lhs)
+    }
+}
+
+impl<K, V> ParsableWithOption for BTreeMap<K, V>
+    where K: ParsableValue + Ord, V: ParsableValue
+{
+    fn parse_arg<I>(iter: &mut Peekable<I>, option: &String) -> ParseResult<Self>
+        where I: Iterator<Item = (usize, String)>
+    {
+        let result = parse_key_value(iter, option);
+        match result.parsed {
+            Some(Ok((key, value))) => {
+                let mut map = BTreeMap::new();
+                map.insert(key, value);
+                ParseResult::success(map, None)
+            },
+            Some(Err(err)) => ParseResult::err(err),
+            None => ParseResult::none(),
+        }
+    }
+
+    fn assign(mut lhs: Self, rhs: Self) -> Self {
+        lhs.extend(rhs);
+        lhs
+    }
+}
+
+impl<K, V> FormattableWithOption for BTreeMap<K, V>
+    where K: FormattableValue, V: FormattableValue
+{
+    fn format_arg(&self, option: &str) -> Vec<String> {
+        format_entries(self.iter(), option)
+    }
+}
+
+impl<K, V> ParsableMap for BTreeMap<K, V>
+    where K: ParsableValue + Ord + Display, V: ParsableValue + ParsableWithOption
+{
+    fn merge(mut lhs: Self, rhs: Self, policy: DuplicateKeys, option: &str) -> Result<Self> {
+        for (key, value) in rhs {
+            let value = match lhs.remove(&key) {
+                Some(old) => merge_value(&key, old, value, policy, option)?,
+                None => value,
+            };
+            lhs.insert(key, value);
+        }
+        Ok(lhs)
+    }
+}
+
+fn format_entries<'a, K, V, I>(entries: I, option: &str) -> Vec<String>
+    where K: FormattableValue + 'a, V: FormattableValue + 'a, I: Iterator<Item = (&'a K, &'a V)>
+{
+    entries.flat_map(|(key, value)| {
+        let key = key.format_value();
+        value.format_values().into_iter().map(move |value| format!("{}={}={}", option, key, value))
+    }).collect()
+}
+
+/// Fails like `ParsableMap::merge` with `DuplicateKeys::Error` if a key of `rhs` is in `lhs`,
+/// without taking either map.
+pub fn check_duplicate_keys<'a, M, K, V>(lhs: &'a M, rhs: &'a M, option: &str) -> Result<()>
+    where &'a M: IntoIterator<Item = (&'a K, &'a V)>, K: PartialEq + Display + 'a, V: 'a
+{
+    for (key, _) in rhs {
+        if lhs.into_iter().any(|(old, _)| old == key) {
+            return Err(Error::DuplicateKey(option.to_string(), key.to_string()));
+        }
+    }
+    Ok(())
+}
+
+fn merge_value<K: Display, V: ParsableWithOption>(key: &K, old: V, new: V, policy: DuplicateKeys, option: &str) -> Result<V> {
+    match policy {
+        DuplicateKeys::Error => Err(Error::DuplicateKey(option.to_string(), key.to_string())),
+        DuplicateKeys::LastWins => Ok(new),
+        DuplicateKeys::Collect => Ok(V::assign(old, new)),
+    }
+}
+
+#[test]
+pub fn test_parsable_for_map() {
+    let args_vec = ["--set", "a=1", "--set", "b=2", "--set", "a=3", "--set", "c"];
+    let mut args = args_vec.iter()
+        .map(|x| x.to_string())
+        .enumerate()
+        .peekable();
+
+    let tmp1 = <HashMap<String, u32> as ParsableWithOption>::parse_arg(&mut args, &"--set".to_string()).parsed.unwrap().unwrap();
+    let tmp2 = <HashMap<String, u32> as ParsableWithOption>::parse_arg(&mut args, &"--set".to_string()).parsed.unwrap().unwrap();
+    let tmp3 = <HashMap<String, u32> as ParsableWithOption>::parse_arg(&mut args, &"--set".to_string()).parsed.unwrap().unwrap();
+
+    let result = <HashMap<String, u32> as ParsableWithOption>::assign(tmp1.clone(), tmp2.clone());
+    assert_eq!(result.len(), 2);
+    assert_eq!(result["a"], 1);
+    assert_eq!(result["b"], 2);
+
+    let result = <HashMap<String, u32> as ParsableMap>::merge(result, tmp3.clone(), DuplicateKeys::LastWins, "--set").unwrap();
+    assert_eq!(result["a"], 3);
+
+    let result = <HashMap<String, u32> as ParsableMap>::merge(tmp1.clone(), tmp3.clone(), DuplicateKeys::Error, "--set");
+    assert_eq!(result, Err(Error::DuplicateKey("--set".into(), "a".into())));
+    assert_eq!(check_duplicate_keys(&tmp1, &tmp3, "--set"), Err(Error::DuplicateKey("--set".into(), "a".into())));
+    assert_eq!(check_duplicate_keys(&tmp1, &tmp2, "--set"), Ok(()));
+
+    let result = <HashMap<String, u32> as ParsableWithOption>::parse_arg(&mut args, &"--set".to_string());
+    assert_eq!(result.parsed, Some(Err(Error::InvalidValue("--set".into(), "c".into(), "expected KEY=VALUE".into()))));
+}
+
+#[test]
+pub fn test_parsable_for_map_collect() {
+    let args_vec = ["-D", "a=1", "-D", "a=2"];
+    let mut args = args_vec.iter()
+        .map(|x| x.to_string())
+        .enumerate()
+        .peekable();
+
+    let tmp1 = <BTreeMap<String, Vec<u32>> as ParsableWithOption>::parse_arg(&mut args, &"-D".to_string()).parsed.unwrap().unwrap();
+    let tmp2 = <BTreeMap<String, Vec<u32>> as ParsableWithOption>::parse_arg(&mut args, &"-D".to_string()).parsed.unwrap().unwrap();
+
+    let result = <BTreeMap<String, Vec<u32>> as ParsableMap>::merge(tmp1, tmp2, DuplicateKeys::Collect, "-D").unwrap();
+    assert_eq!(result["a"], vec![1, 2]);
+}
+
+/// Parse an option followed by exactly `count` values.
+///
+/// The first value may also be attached like "--foo=bar". Fails with `Error::MissingValues` if
+/// the arguments end early.
+pub fn parse_option_values<I>(iter: &mut Peekable<I>, option: &str, count: usize) -> ParseResult<Vec<String>>
+    where I: Iterator<Item = (usize, String)>
+{
+    let result = parse_string_arg(iter, option);
+    match result.parsed {
+        Some(Ok(first)) => {
+            let mut values = vec![first];
+            while values.len() < count {
+                match iter.next() {
+                    Some((_, value)) => values.push(value),
+                    None => return ParseResult::err(Error::MissingValues(option.to_string(), count, values.len())),
+                }
+            }
+            ParseResult::success(values, result.blacklist)
+        },
+        Some(Err(Error::UnexpectedEnd)) => ParseResult::err(Error::MissingValues(option.to_string(), count, 0)),
+        Some(Err(err)) => ParseResult::err(err),
+        None => ParseResult::none(),
+    }
+}
+
+/// Format an option followed by several values, the first one attached like "--foo=bar".
+fn format_option_values(option: &str, values: &[String]) -> Vec<String> {
+    match values.split_first() {
+        Some((first, rest)) => Some(format!("{}={}", option, first)).into_iter().chain(rest.iter().cloned()).collect(),
+        None => vec![option.to_string()],
+    }
+}
+
+fn convert_value<T: ParsableValue>(option: &str, value: String) -> Result<T> {
+    T::parse_value(&value).map_err(|reason| Error::InvalidValue(option.to_string(), value, reason))
+}
+
+macro_rules! impl_parsable_for_tuple {
+    ($count:expr; $($ty:ident),*) => {
+        impl<$($ty : ParsableValue),*> ParsableWithOption for ($($ty,)*) {
+            fn parse_arg<I>(iter: &mut Peekable<I>, option: &String) -> ParseResult<Self>
+                where I: Iterator<Item = (usize, String)>
+            {
+                let result = parse_option_values(iter, option, $count);
+                match result.parsed {
+                    Some(Ok(values)) => {
+                        let mut values = values.into_iter();
+                        let tuple = ($(
+                            match convert_value::<$ty>(option, values.next().unwrap()) {
+                                Ok(val) => val,
+                                Err(err) => return ParseResult::err(err),
+                            },
+                        )*);
+                        ParseResult::success(tuple, result.blacklist)
+                    },
+                    Some(Err(err)) => ParseResult::err(err),
+                    None => ParseResult::none(),
+                }
+            }
+        }
+
+        impl<$($ty : FormattableValue),*> FormattableWithOption for ($($ty,)*) {
+            #[allow(non_snake_case)]
+            fn format_arg(&self, option: &str) -> Vec<String> {
+                let ($(ref $ty,)*) = *self;
+                format_option_values(option, &[$($ty.format_value()),*])
+            }
+        }
+    }
+}
+
+impl_parsable_for_tuple!(2; A, B);
+impl_parsable_for_tuple!(3; A, B, C);
+impl_parsable_for_tuple!(4; A, B, C, D);
+
+impl<T : ParsableValue, const N: usize> ParsableWithOption for [T; N] {
+    fn parse_arg<I>(iter: &mut Peekable<I>, option: &String) -> ParseResult<Self>
+        where I: Iterator<Item = (usize, String)>
+    {
+        // Without elements there is no value to take, so the option is given like a flag.
+        let result = if N == 0 {
+            <bool as ParsableWithOption>::parse_arg(iter, option).map(|_| vec![])
+        }
+        else {
+            parse_option_values(iter, option, N)
+        };
+        match result.parsed {
+            Some(Ok(values)) => {
+                let mut elements = Vec::with_capacity(N);
+                for value in values {
+                    match convert_value(option, value) {
+                        Ok(val) => elements.push(val),
+                        Err(err) => return ParseResult::err(err),
+                    }
+                }
+                match <[T; N]>::try_from(elements) {
+                    Ok(array) => ParseResult::success(array, result.blacklist),
+                    Err(_) => unreachable!(),
+                }
             },
-            _ => {},
+            Some(Err(err)) => ParseResult::err(err),
+            None => ParseResult::none(),
         }
+    }
+}
+
+impl<T : FormattableValue, const N: usize> FormattableWithOption for [T; N] {
+    fn format_arg(&self, option: &str) -> Vec<String> {
+        let values: Vec<String> = self.iter().map(|val| val.format_value()).collect();
+        format_option_values(option, &values)
+    }
+}
+
+#[test]
+pub fn test_parsable_for_tuple() {
+    let args_vec = ["--resize", "800", "600", "--range=a", "b", "--resize", "1", "x", "--resize", "1"];
+    let mut args = args_vec.iter()
+        .map(|x| x.to_string())
+        .enumerate()
+        .peekable();
+
+    let result = <(u32, u32) as ParsableWithOption>::parse_arg(&mut args, &"--resize".to_string());
+    assert_eq!(result.parsed, Some(Ok((800, 600))));
+    assert_eq!(result.blacklist, Some("--resize".into()));
+
+    let result = <[String; 2] as ParsableWithOption>::parse_arg(&mut args, &"--range".to_string());
+    assert_eq!(result.parsed, Some(Ok(["a".to_string(), "b".to_string()])));
+
+    let result = <(u32, u32) as ParsableWithOption>::parse_arg(&mut args, &"--resize".to_string());
+    assert_eq!(result.parsed, Some(Err(Error::InvalidValue("--resize".into(), "x".into(), "invalid digit found in string".into()))));
+
+    let result = <(u32, u32, u32) as ParsableWithOption>::parse_arg(&mut args, &"--resize".to_string());
+    assert_eq!(result.parsed, Some(Err(Error::MissingValues("--resize".into(), 3, 1))));
+}
+
+#[test]
+pub fn test_parsable_for_empty_array() {
+    let args_vec = ["--none", "x"];
+    let mut args = args_vec.iter()
+        .map(|x| x.to_string())
+        .enumerate()
+        .peekable();
+
+    let result = <[u32; 0] as ParsableWithOption>::parse_arg(&mut args, &"--none".to_string());
+    assert_eq!(result.parsed, Some(Ok([])));
+    assert_eq!(result.blacklist, Some("--none".into()));
+    assert_eq!(args.next(), Some((1, "x".into())));
+    assert_eq!(<[u32; 0] as FormattableWithOption>::format_arg(&[], "--none"), vec!["--none"]);
+}
+
+#[test]
+pub fn test_format_arg() {
+    assert_eq!(<u32 as FormattableWithOption>::format_arg(&42, "--foo"), vec!["--foo=42"]);
+    assert_eq!(<bool as FormattableWithOption>::format_arg(&false, "--foo"), Vec::<String>::new());
+    assert_eq!(<Vec<String> as FormattableWithOption>::format_arg(&vec!["a".into(), "b".into()], "--foo"), vec!["--foo=a", "--foo=b"]);
+    assert_eq!(<(u32, f64) as FormattableWithOption>::format_arg(&(1, 0.5), "--foo"), vec!["--foo=1", "0.5"]);
+
+    let mut map = BTreeMap::new();
+    map.insert("a".to_string(), vec![1, 2]);
+    assert_eq!(<BTreeMap<String, Vec<u32>> as FormattableWithOption>::format_arg(&map, "-D"), vec!["-D=a=1", "-D=a=2"]);
+}
 
+impl ParsableWithPosition for String {
+    fn parse_arg<I>(iter: &mut Peekable<I>, position: usize) -> ParseResult<Self>
+        where I: Iterator<Item = (usize, String)>
+    {
         match iter.peek().cloned() {
             Some((pos, ref val)) if pos == position => {
                 iter.next();
@@ -223,6 +1164,12 @@ impl ParsableWithPosition for String {
     }
 }
 
+impl FormattableWithPosition for String {
+    fn format_arg(&self) -> Option<String> {
+        Some(self.clone())
+    }
+}
+
 impl<T : ParsableWithPosition> ParsableWithPosition for Option<T> {
     fn parse_arg<I>(iter: &mut Peekable<I>, position: usize) -> ParseResult<Self>
         where I: Iterator<Item = (usize, String)>
@@ -234,4 +1181,18 @@ impl<T : ParsableWithPosition> ParsableWithPosition for Option<T> {
             None => ParseResult::none(),
         }
     }
+
+    fn possible_values() -> &'static [&'static str] {
+        <T as ParsableWithPosition>::possible_values()
+    }
+
+    fn aliases() -> &'static [&'static str] {
+        <T as ParsableWithPosition>::aliases()
+    }
+}
+
+impl<T : FormattableWithPosition> FormattableWithPosition for Option<T> {
+    fn format_arg(&self) -> Option<String> {
+        self.as_ref().and_then(|val| val.format_arg())
+    }
 }
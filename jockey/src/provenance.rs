@@ -0,0 +1,92 @@
+use std::ops::{Deref, Range};
+
+/// Where the value of a field came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Source {
+    /// Given in the arguments.
+    Args,
+
+    /// Converted from `#[jockey(default_value="...")]`.
+    DefaultValue,
+
+    /// The `Default` of the field type, or of the struct with `#[jockey(default)]`.
+    Default,
+}
+
+/// Where the value of a field came from and which arguments it was parsed from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldMeta {
+    /// Source of the value.
+    pub source: Source,
+
+    /// Indices of the arguments that were taken for the field, counted like positions so the
+    /// path of the executable is 0. Includes the option names as well as the values.
+    pub indices: Vec<usize>,
+
+    /// How often the field was given, including occurrences that were ignored by
+    /// `#[jockey(duplicate_options="first_wins")]`.
+    pub occurrences: usize,
+}
+
+/// Parsed arguments along with a `FieldMeta` for every field, see
+/// `Arguments::parse_args_with_meta`.
+///
+/// Dereferences to the parsed arguments.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Parsed<T> {
+    value: T,
+    fields: Vec<(String, FieldMeta)>,
+}
+
+impl<T> Parsed<T> {
+    /// Creates the result for `value`.
+    ///
+    /// `fallbacks` names every field along with the source of its value if it is not given.
+    /// `taken` names the field and the range of argument indices of every occurrence.
+    pub fn new(value: T, fallbacks: &[(&str, Source)], taken: &[(&str, Range<usize>)]) -> Self {
+        let fields = fallbacks.iter().map(|&(name, fallback)| {
+            let occurrences: Vec<&Range<usize>> = taken.iter().filter(|entry| entry.0 == name).map(|entry| &entry.1).collect();
+            let meta = FieldMeta {
+                source: if occurrences.is_empty() { fallback } else { Source::Args },
+                indices: occurrences.iter().flat_map(|range| (*range).clone()).collect(),
+                occurrences: occurrences.len(),
+            };
+            (name.to_string(), meta)
+        }).collect();
+        Parsed { value, fields }
+    }
+
+    /// The `FieldMeta` of the field with the given name, if there is one.
+    pub fn meta(&self, field: &str) -> Option<&FieldMeta> {
+        self.fields.iter().find(|entry| entry.0 == field).map(|entry| &entry.1)
+    }
+
+    /// The names and `FieldMeta` of all fields in the order they are declared.
+    pub fn fields(&self) -> impl Iterator<Item = (&str, &FieldMeta)> {
+        self.fields.iter().map(|entry| (entry.0.as_str(), &entry.1))
+    }
+
+    /// Returns the parsed arguments.
+    pub fn into_inner(self) -> T {
+        self.value
+    }
+}
+
+impl<T> Deref for Parsed<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.value
+    }
+}
+
+#[test]
+pub fn test_parsed() {
+    let parsed = Parsed::new(42, &[("level", Source::DefaultValue), ("tag", Source::Default)], &[("tag", 1..3), ("tag", 4..5)]);
+    assert_eq!(*parsed, 42);
+    assert_eq!(parsed.meta("level"), Some(&FieldMeta { source: Source::DefaultValue, indices: vec![], occurrences: 0 }));
+    assert_eq!(parsed.meta("tag"), Some(&FieldMeta { source: Source::Args, indices: vec![1, 2, 4], occurrences: 2 }));
+    assert_eq!(parsed.meta("other"), None);
+    assert_eq!(parsed.fields().map(|(name, _)| name).collect::<Vec<&str>>(), vec!["level", "tag"]);
+    assert_eq!(parsed.into_inner(), 42);
+}
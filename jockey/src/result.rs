@@ -9,6 +9,35 @@ pub enum Error {
     UnknownOption(String),
     UnexpectedEnd,
     DuplicateOption(String),
+
+    /// A value could not be converted. Contains the option, the offending value and the reason.
+    InvalidValue(String, String, String),
+
+    /// A map option received the same key twice. Contains the option and the key.
+    DuplicateKey(String, String),
+
+    /// An option got fewer values than it takes. Contains the option, the expected and the found
+    /// number of values.
+    MissingValues(String, usize, usize),
+
+    /// An abbreviated option matches several options. Contains the abbreviation and the
+    /// candidates.
+    AmbiguousOption(String, Vec<String>),
+
+    /// A required option or positional argument was not given. Contains its name.
+    MissingArgument(String),
+
+    /// The arguments were rejected by a validation. Contains the message.
+    Custom(String),
+
+    /// The options of a `Command` are defined inconsistently, e.g. twice. Contains the reason.
+    InvalidDefinition(String),
+
+    /// "--help" was given, so the help text should be shown instead of running the program.
+    HelpRequested,
+
+    /// "--version" was given, so the version should be shown instead of running the program.
+    VersionRequested,
 }
 
 impl error::Error for Error {
@@ -17,23 +46,53 @@ impl error::Error for Error {
             Error::UnknownOption(_) => "Unknown option",
             Error::UnexpectedEnd => "Unexpected end of arguments vector",
             Error::DuplicateOption(_) => "Duplicate option encountered",
+            Error::InvalidValue(_, _, _) => "Invalid value",
+            Error::DuplicateKey(_, _) => "Duplicate key encountered",
+            Error::MissingValues(_, _, _) => "Missing values",
+            Error::AmbiguousOption(_, _) => "Ambiguous option",
+            Error::MissingArgument(_) => "Missing required argument",
+            Error::Custom(_) => "Invalid arguments",
+            Error::InvalidDefinition(_) => "Invalid definition of the arguments",
+            Error::HelpRequested => "Help requested",
+            Error::VersionRequested => "Version requested",
         }
     }
     
+    #[allow(bare_trait_objects)]
     fn cause(&self) -> Option<&error::Error> {
         None
     }
 }
 
 impl fmt::Display for Error {
+    #[allow(deprecated)]
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
             Error::UnknownOption(which) => write!(f, "{}: {}", self.description(), which),
             Error::DuplicateOption(which) => write!(f, "{}: {}", self.description(), which),
+            Error::InvalidValue(option, value, reason) => write!(f, "Invalid value '{}' for {}: {}", value, option, reason),
+            Error::DuplicateKey(option, key) => write!(f, "Duplicate key encountered for {}: {}", option, key),
+            Error::MissingValues(option, expected, found) => write!(f, "Missing values for {}: expected {} but found {}", option, expected, found),
+            Error::AmbiguousOption(which, candidates) => write!(f, "Ambiguous option: {} (could be {})", which, candidates.join(", ")),
+            Error::MissingArgument(which) => write!(f, "Missing required argument: {}", which),
+            Error::Custom(message) => write!(f, "{}", message),
+            Error::InvalidDefinition(reason) => write!(f, "{}: {}", self.description(), reason),
             _ => write!(f, "{}", self.description()),
         }
     }
 }
 
+impl From<String> for Error {
+    fn from(message: String) -> Self {
+        Error::Custom(message)
+    }
+}
+
+impl<'a> From<&'a str> for Error {
+    fn from(message: &'a str) -> Self {
+        Error::Custom(message.to_string())
+    }
+}
+
 /// Result type for this crate.
 pub type Result<T> = std::result::Result<T, Error>;
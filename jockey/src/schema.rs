@@ -0,0 +1,409 @@
+use help::render_help;
+
+/// Description of a set of command-line arguments, see `Arguments::schema`.
+///
+/// The help text is rendered from it and `to_json` exports it for other tools. There are no option
+/// aliases, environment variables or subcommands to describe.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Schema {
+
+    /// Name that takes the place of the program name, if any.
+    pub name: Option<String>,
+
+    /// Version shown for "--version", if any.
+    pub version: Option<String>,
+
+    /// Text shown above the usage line in the help, if any.
+    pub about: Option<String>,
+
+    /// Options in the order they are shown, including the built-in "--help" and "--version".
+    pub options: Vec<OptionSchema>,
+
+    /// Positional arguments sorted by position, followed by the catch-all for remaining
+    /// arguments and the trailing arguments if there are any.
+    pub arguments: Vec<ArgumentSchema>,
+}
+
+/// Description of an option.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct OptionSchema {
+
+    /// Long name like "--foo", if any.
+    pub long: Option<String>,
+
+    /// Short name like "-f", if any.
+    pub short: Option<String>,
+
+    /// Placeholders of the values that follow the option, e.g. "SIZE" twice for a pair. Empty
+    /// for flags.
+    pub value_names: Vec<String>,
+
+    /// Whether the value may be left out, in which case it must be attached like "--foo=bar".
+    pub optional_value: bool,
+
+    /// Rust type of the field.
+    pub type_name: String,
+
+    /// Whether the option may be given several times.
+    pub multiple: bool,
+
+    /// Whether parsing fails if the option is not given.
+    pub required: bool,
+
+    /// Value used if the option is not given, if declared.
+    pub default_value: Option<String>,
+
+    /// The values that are accepted, if restricted.
+    pub possible_values: Vec<String>,
+
+    /// Range that the values must lie in like "1..=65535", if restricted.
+    pub range: Option<String>,
+
+    /// Description from the doc comment.
+    pub help: String,
+}
+
+/// Description of a positional argument, of the catch-all for remaining arguments or of the
+/// trailing arguments.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ArgumentSchema {
+
+    /// Placeholder like "FILE".
+    pub name: String,
+
+    /// Position counted from the path of the executable, `None` for the catch-all. For trailing
+    /// arguments the position they start at, if any.
+    pub position: Option<usize>,
+
+    /// Rust type of the field.
+    pub type_name: String,
+
+    /// Whether parsing fails if the argument is not given.
+    pub required: bool,
+
+    /// Value used if the argument is not given, if declared.
+    pub default_value: Option<String>,
+
+    /// The values that are accepted, if restricted.
+    pub possible_values: Vec<String>,
+
+    /// Range that the values must lie in like "1..=65535", if restricted.
+    pub range: Option<String>,
+
+    /// Whether these are the arguments taken verbatim after "--".
+    pub trailing: bool,
+
+    /// Description from the doc comment.
+    pub help: String,
+}
+
+impl OptionSchema {
+    /// Names and values as shown in the help, e.g. "-s, --size <SIZE>".
+    pub fn display_name(&self) -> String {
+        let mut names = match (self.short.as_ref(), self.long.as_ref()) {
+            (Some(short), Some(long)) => format!("{}, {}", short, long),
+            (Some(short), None) => short.clone(),
+            (None, Some(long)) => format!("    {}", long),
+            (None, None) => String::new(),
+        };
+        if self.optional_value {
+            names.push_str(&format!("[=<{}>]", self.value_names.join(" ")));
+        }
+        else {
+            for name in self.value_names.iter() {
+                names.push_str(&format!(" <{}>", name));
+            }
+        }
+        names
+    }
+}
+
+impl ArgumentSchema {
+    /// Name as shown in the usage line, e.g. "<FILE>", "[FILE]", "[REST]..." or "[-- ARGS...]".
+    pub fn display_name(&self) -> String {
+        if self.trailing {
+            return format!("[-- {}...]", self.name);
+        }
+        match (self.position, self.required) {
+            (None, _) => format!("[{}]...", self.name),
+            (Some(_), true) => format!("<{}>", self.name),
+            (Some(_), false) => format!("[{}]", self.name),
+        }
+    }
+}
+
+/// The description followed by the default and allowed values.
+fn describe(help: &str, default_value: &Option<String>, possible_values: &[String], range: &Option<String>) -> String {
+    let mut description = help.to_string();
+    if let Some(ref value) = *default_value {
+        description.push_str(&format!(" [default: {}]", value));
+    }
+    if !possible_values.is_empty() {
+        description.push_str(&format!(" [possible values: {}]", possible_values.join(", ")));
+    }
+    if let Some(ref range) = *range {
+        description.push_str(&format!(" [range: {}]", range));
+    }
+    description.trim().to_string()
+}
+
+impl Schema {
+    /// Returns the usage line, e.g. "Usage: program [OPTIONS] <FILE>".
+    pub fn usage(&self, program: &str) -> String {
+        let mut usage = format!("Usage: {} [OPTIONS]", self.name.as_deref().unwrap_or(program));
+        for argument in self.arguments.iter() {
+            usage.push(' ');
+            usage.push_str(&argument.display_name());
+        }
+        usage
+    }
+
+    /// Returns the help text with the usage line and a description of every argument and option.
+    pub fn help(&self, program: &str) -> String {
+        let arguments: Vec<(String, String)> = self.arguments.iter()
+            .map(|argument| (argument.display_name(), describe(&argument.help, &argument.default_value, &argument.possible_values, &argument.range)))
+            .collect();
+        let options: Vec<(String, String)> = self.options.iter()
+            .map(|option| (option.display_name(), describe(&option.help, &option.default_value, &option.possible_values, &option.range)))
+            .collect();
+
+        let arguments: Vec<(&str, &str)> = arguments.iter().map(|entry| (entry.0.as_str(), entry.1.as_str())).collect();
+        let options: Vec<(&str, &str)> = options.iter().map(|entry| (entry.0.as_str(), entry.1.as_str())).collect();
+        let help = render_help(&self.usage(program), &arguments, &options);
+        match self.about {
+            Some(ref about) => format!("{}\n\n{}", about, help),
+            None => help,
+        }
+    }
+
+    /// Returns the line that is shown for "--version", e.g. "program 1.0.0".
+    pub fn version(&self, program: &str) -> String {
+        let program = self.name.as_deref().unwrap_or(program);
+        match self.version {
+            Some(ref version) => format!("{} {}", program, version),
+            None => program.to_string(),
+        }
+    }
+
+    /// Returns a bash completion script for the program.
+    ///
+    /// It completes the option names, the possible values after an option and, for other
+    /// arguments, the possible values of the positional arguments. Anything else falls back to
+    /// the completion of file names. The script is loaded with `source` or from the completions
+    /// directory of bash.
+    pub fn bash_completion(&self, program: &str) -> String {
+        let program = self.name.as_deref().unwrap_or(program);
+        let function: String = program.chars().map(|c| if c.is_ascii_alphanumeric() { c } else { '_' }).collect();
+        let names: Vec<&str> = self.options.iter().flat_map(|option| option.long.iter().chain(option.short.iter())).map(|name| name.as_str()).collect();
+
+        let mut script = format!("_{}() {{\n", function);
+        script.push_str("    local cur=\"${COMP_WORDS[COMP_CWORD]}\"\n");
+        script.push_str("    local prev=\"${COMP_WORDS[COMP_CWORD-1]}\"\n");
+        script.push_str("    case \"$prev\" in\n");
+        for option in self.options.iter().filter(|option| !option.value_names.is_empty() && !option.optional_value) {
+            let names: Vec<&str> = option.long.iter().chain(option.short.iter()).map(|name| name.as_str()).collect();
+            script.push_str(&format!("        {})\n", names.join("|")));
+            script.push_str(&format!("            COMPREPLY=($(compgen -W {} -- \"$cur\"))\n", shell_quote(&option.possible_values.join(" "))));
+            script.push_str("            return 0\n");
+            script.push_str("            ;;\n");
+        }
+        script.push_str("    esac\n");
+        script.push_str("    if [[ \"$cur\" == -* ]]; then\n");
+        script.push_str(&format!("        COMPREPLY=($(compgen -W {} -- \"$cur\"))\n", shell_quote(&names.join(" "))));
+        script.push_str("    else\n");
+        let values: Vec<&str> = self.arguments.iter().flat_map(|argument| argument.possible_values.iter()).map(|value| value.as_str()).collect();
+        script.push_str(&format!("        COMPREPLY=($(compgen -W {} -- \"$cur\"))\n", shell_quote(&values.join(" "))));
+        script.push_str("    fi\n");
+        script.push_str("}\n");
+        script.push_str(&format!("complete -o default -F _{} {}\n", function, shell_quote(program)));
+        script
+    }
+
+    /// Serializes the schema to a JSON object.
+    ///
+    /// Missing values are `null`. The keys are the names of the fields.
+    pub fn to_json(&self) -> String {
+        let mut json = JsonObject::new();
+        json.field("name", json_option(&self.name));
+        json.field("version", json_option(&self.version));
+        json.field("about", json_option(&self.about));
+        json.field("options", json_array(self.options.iter().map(|option| option.to_json())));
+        json.field("arguments", json_array(self.arguments.iter().map(|argument| argument.to_json())));
+        json.finish()
+    }
+}
+
+impl OptionSchema {
+    /// Serializes the option to a JSON object, see `Schema::to_json`.
+    pub fn to_json(&self) -> String {
+        let mut json = JsonObject::new();
+        json.field("long", json_option(&self.long));
+        json.field("short", json_option(&self.short));
+        json.field("value_names", json_array(self.value_names.iter().map(|name| json_string(name))));
+        json.field("optional_value", self.optional_value.to_string());
+        json.field("type_name", json_string(&self.type_name));
+        json.field("multiple", self.multiple.to_string());
+        json.field("required", self.required.to_string());
+        json.field("default_value", json_option(&self.default_value));
+        json.field("possible_values", json_array(self.possible_values.iter().map(|value| json_string(value))));
+        json.field("range", json_option(&self.range));
+        json.field("help", json_string(&self.help));
+        json.finish()
+    }
+}
+
+impl ArgumentSchema {
+    /// Serializes the argument to a JSON object, see `Schema::to_json`.
+    pub fn to_json(&self) -> String {
+        let mut json = JsonObject::new();
+        json.field("name", json_string(&self.name));
+        json.field("position", self.position.map(|position| position.to_string()).unwrap_or_else(|| String::from("null")));
+        json.field("type_name", json_string(&self.type_name));
+        json.field("required", self.required.to_string());
+        json.field("default_value", json_option(&self.default_value));
+        json.field("possible_values", json_array(self.possible_values.iter().map(|value| json_string(value))));
+        json.field("range", json_option(&self.range));
+        json.field("trailing", self.trailing.to_string());
+        json.field("help", json_string(&self.help));
+        json.finish()
+    }
+}
+
+/// Quote a string for the shell with single quotes.
+fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "'\\''"))
+}
+
+/// Builds a JSON object from already serialized values.
+struct JsonObject {
+    json: String,
+}
+
+impl JsonObject {
+    fn new() -> Self {
+        JsonObject { json: String::from("{") }
+    }
+
+    fn field(&mut self, key: &str, value: String) {
+        if self.json.len() > 1 {
+            self.json.push(',');
+        }
+        self.json.push_str(&json_string(key));
+        self.json.push(':');
+        self.json.push_str(&value);
+    }
+
+    fn finish(mut self) -> String {
+        self.json.push('}');
+        self.json
+    }
+}
+
+/// Quote and escape a string as JSON.
+fn json_string(value: &str) -> String {
+    let mut json = String::from("\"");
+    for c in value.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            '\r' => json.push_str("\\r"),
+            '\t' => json.push_str("\\t"),
+            c if (c as u32) < 0x20 => json.push_str(&format!("\\u{:04x}", c as u32)),
+            c => json.push(c),
+        }
+    }
+    json.push('"');
+    json
+}
+
+fn json_option(value: &Option<String>) -> String {
+    match *value {
+        Some(ref value) => json_string(value),
+        None => String::from("null"),
+    }
+}
+
+fn json_array<I: Iterator<Item = String>>(values: I) -> String {
+    format!("[{}]", values.collect::<Vec<String>>().join(","))
+}
+
+#[test]
+pub fn test_json_string() {
+    assert_eq!(json_string("a \"b\"\n\\"), r#""a \"b\"\n\\""#);
+    assert_eq!(json_string("\u{1}"), r#""\u0001""#);
+}
+
+#[test]
+pub fn test_schema() {
+    let schema = Schema {
+        name: Some("tool".into()),
+        version: Some("1.0".into()),
+        about: None,
+        options: vec![OptionSchema {
+            long: Some("--size".into()),
+            short: Some("-s".into()),
+            value_names: vec!["SIZE".into(), "SIZE".into()],
+            type_name: "(u32,u32)".into(),
+            default_value: Some("1 1".into()),
+            help: "Image size".into(),
+            ..OptionSchema::default()
+        }],
+        arguments: vec![ArgumentSchema {
+            name: "FILE".into(),
+            position: Some(1),
+            type_name: "String".into(),
+            required: true,
+            ..ArgumentSchema::default()
+        }],
+    };
+
+    assert_eq!(schema.usage("prog"), "Usage: tool [OPTIONS] <FILE>");
+    assert_eq!(schema.version("prog"), "tool 1.0");
+    assert_eq!(schema.help("prog"), "Usage: tool [OPTIONS] <FILE>\n\nArguments:\n  <FILE>\n\nOptions:\n  -s, --size <SIZE> <SIZE>  Image size [default: 1 1]\n");
+    assert_eq!(schema.to_json(), concat!(
+        r#"{"name":"tool","version":"1.0","about":null,"options":[{"long":"--size","short":"-s","value_names":["SIZE","SIZE"],"#,
+        r#""optional_value":false,"type_name":"(u32,u32)","multiple":false,"required":false,"default_value":"1 1","possible_values":[],"range":null,"help":"Image size"}],"#,
+        r#""arguments":[{"name":"FILE","position":1,"type_name":"String","required":true,"default_value":null,"possible_values":[],"range":null,"trailing":false,"help":""}]}"#));
+}
+
+#[test]
+pub fn test_bash_completion() {
+    let schema = Schema {
+        options: vec![OptionSchema {
+            long: Some("--level".into()),
+            short: Some("-l".into()),
+            value_names: vec!["LEVEL".into()],
+            possible_values: vec!["debug".into(), "info".into()],
+            ..OptionSchema::default()
+        }, OptionSchema {
+            long: Some("--verbose".into()),
+            ..OptionSchema::default()
+        }],
+        arguments: vec![ArgumentSchema {
+            name: "MODE".into(),
+            position: Some(1),
+            possible_values: vec!["fast".into(), "it's slow".into()],
+            ..ArgumentSchema::default()
+        }],
+        ..Schema::default()
+    };
+
+    assert_eq!(schema.bash_completion("my-prog"), concat!(
+        "_my_prog() {\n",
+        "    local cur=\"${COMP_WORDS[COMP_CWORD]}\"\n",
+        "    local prev=\"${COMP_WORDS[COMP_CWORD-1]}\"\n",
+        "    case \"$prev\" in\n",
+        "        --level|-l)\n",
+        "            COMPREPLY=($(compgen -W 'debug info' -- \"$cur\"))\n",
+        "            return 0\n",
+        "            ;;\n",
+        "    esac\n",
+        "    if [[ \"$cur\" == -* ]]; then\n",
+        "        COMPREPLY=($(compgen -W '--level -l --verbose' -- \"$cur\"))\n",
+        "    else\n",
+        "        COMPREPLY=($(compgen -W 'fast it'\\''s slow' -- \"$cur\"))\n",
+        "    fi\n",
+        "}\n",
+        "complete -o default -F _my_prog 'my-prog'\n"));
+}
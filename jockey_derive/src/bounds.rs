@@ -0,0 +1,161 @@
+use parser;
+
+use proc_macro2::{TokenStream, TokenTree};
+use quote::ToTokens;
+use syn::{Generics, Ident, Type};
+
+/// Whether the tokens refer to one of the type parameters.
+fn mentions_any(tokens: TokenStream, params: &[Ident]) -> bool {
+    tokens.into_iter().any(|token| match token {
+        TokenTree::Ident(ref ident) => params.contains(ident),
+        TokenTree::Group(ref group) => mentions_any(group.stream(), params),
+        _ => false,
+    })
+}
+
+/// Bounds that the generated code needs for a field to convert its values one by one.
+fn get_conversion_bounds(inner: &Type, conversion: &parser::Conversion) -> Vec<TokenStream> {
+    let mut bounds = Vec::new();
+    if conversion.parse_with.is_none() {
+        bounds.push(quote!{ #inner: jockey::ParsableValue });
+    }
+    bounds
+}
+
+/// Bounds that the generated code needs for a field to format its values one by one.
+fn get_format_conversion_bounds(inner: &Type, conversion: &parser::Conversion) -> Vec<TokenStream> {
+    let mut bounds = Vec::new();
+    if conversion.format_with.is_some() {
+        return bounds;
+    }
+    if conversion.parse_with.is_some() {
+        bounds.push(quote!{ #inner: std::string::ToString });
+        return bounds;
+    }
+    bounds.push(quote!{ #inner: jockey::FormattableValue });
+    // Values are renamed with the names of ParsableValue.
+    if conversion.rename_all.is_some() {
+        bounds.push(quote!{ #inner: jockey::ParsableValue });
+    }
+    bounds
+}
+
+/// Bounds that the generated parser needs for a field.
+fn get_field_bounds(field: &parser::Field, settings: &parser::StructSettings) -> Vec<TokenStream> {
+    let mut bounds = Vec::new();
+    let (ty, fallback) = match *field {
+        parser::Field::Ordinary(ref field) => {
+            let ty = &field.ty;
+            let (_, inner) = parser::split_type(ty);
+            match parser::split_optional_value_type(ty) {
+                Some(innermost) => bounds.extend(get_conversion_bounds(&innermost, &field.conversion)),
+                None if field.conversion.is_custom() => bounds.extend(get_conversion_bounds(&inner, &field.conversion)),
+                None => {
+                    bounds.push(quote!{ #ty: jockey::ParsableWithOption });
+                    if field.duplicate_keys.is_some() {
+                        // update_from_args takes the current map to merge into it.
+                        bounds.push(quote!{ #ty: jockey::ParsableMap + Default });
+                    }
+                    if let parser::Fallback::DefaultValue(_) = field.fallback {
+                        bounds.push(quote!{ #inner: jockey::ParsableValue });
+                    }
+                },
+            }
+            (ty, &field.fallback)
+        },
+        parser::Field::Position(ref field) => {
+            let ty = &field.ty;
+            let (_, inner) = parser::split_type(ty);
+            if field.conversion.is_custom() {
+                bounds.extend(get_conversion_bounds(&inner, &field.conversion));
+            }
+            else {
+                bounds.push(quote!{ #ty: jockey::ParsableWithPosition });
+                if let parser::Fallback::DefaultValue(_) = field.fallback {
+                    bounds.push(quote!{ #inner: jockey::ParsableValue });
+                }
+            }
+            (ty, &field.fallback)
+        },
+        parser::Field::Unknown(ref field) => {
+            let ty = &field.ty;
+            bounds.push(quote!{ #ty: std::iter::Extend<String> + Default });
+            return bounds;
+        },
+        // Only Vec<String> and Vec<OsString> are accepted, which need no bounds.
+        parser::Field::Trailing(_) => return bounds,
+    };
+    if *fallback == parser::Fallback::Default && !settings.default {
+        bounds.push(quote!{ #ty: Default });
+    }
+    bounds
+}
+
+/// Bounds that the generated `to_args` needs for a field.
+fn get_field_format_bounds(field: &parser::Field, _settings: &parser::StructSettings) -> Vec<TokenStream> {
+    let mut bounds = Vec::new();
+    match *field {
+        parser::Field::Ordinary(ref field) => {
+            let ty = &field.ty;
+            let (_, inner) = parser::split_type(ty);
+            match parser::split_optional_value_type(ty) {
+                Some(innermost) => bounds.extend(get_format_conversion_bounds(&innermost, &field.conversion)),
+                None if field.conversion.is_custom() => bounds.extend(get_format_conversion_bounds(&inner, &field.conversion)),
+                None => bounds.push(quote!{ #ty: jockey::FormattableWithOption }),
+            }
+        },
+        parser::Field::Position(ref field) => {
+            let ty = &field.ty;
+            let (_, inner) = parser::split_type(ty);
+            if field.conversion.is_custom() {
+                bounds.extend(get_format_conversion_bounds(&inner, &field.conversion));
+            }
+            else {
+                bounds.push(quote!{ #ty: jockey::FormattableWithPosition });
+            }
+        },
+        parser::Field::Unknown(_) | parser::Field::Trailing(_) => {},
+    }
+    bounds
+}
+
+/// Adds the bounds that `Arguments` needs for the fields whose types refer to type parameters to
+/// the where clause.
+pub fn add_field_bounds(generics: &Generics, data: &parser::StructData) -> Generics {
+    add_bounds(generics, data, get_field_bounds)
+}
+
+/// Adds the bounds that `ToArgs` needs for the fields whose types refer to type parameters to the
+/// where clause.
+pub fn add_field_format_bounds(generics: &Generics, data: &parser::StructData) -> Generics {
+    add_bounds(generics, data, get_field_format_bounds)
+}
+
+fn add_bounds<F>(generics: &Generics, data: &parser::StructData, get_bounds: F) -> Generics
+    where F: Fn(&parser::Field, &parser::StructSettings) -> Vec<TokenStream>
+{
+    let mut generics = generics.clone();
+    let params: Vec<Ident> = generics.type_params().map(|param| param.ident.clone()).collect();
+    if params.is_empty() {
+        return generics;
+    }
+
+    let bounds: Vec<TokenStream> = data.fields.iter()
+        .filter(|field| {
+            let ty = match *field {
+                parser::Field::Ordinary(ref field) => &field.ty,
+                parser::Field::Position(ref field) => &field.ty,
+                parser::Field::Unknown(ref field) => &field.ty,
+                parser::Field::Trailing(ref field) => &field.ty,
+            };
+            mentions_any(ty.into_token_stream(), &params)
+        })
+        .flat_map(|field| get_bounds(field, &data.settings))
+        .collect();
+
+    let where_clause = generics.make_where_clause();
+    for bound in bounds {
+        where_clause.predicates.push(parse_quote!(#bound));
+    }
+    generics
+}
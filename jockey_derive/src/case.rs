@@ -0,0 +1,80 @@
+/// Splits an identifier like `FooBar` or `foo_bar` into lowercase words.
+pub fn split_words(ident: &str) -> Vec<String> {
+    let chars: Vec<char> = ident.chars().collect();
+    let mut words = Vec::new();
+    let mut current = String::new();
+    for (i, &c) in chars.iter().enumerate() {
+        if c == '_' || c == '-' {
+            if !current.is_empty() {
+                words.push(current);
+                current = String::new();
+            }
+            continue;
+        }
+        // A new word starts at an uppercase letter following a lowercase letter or digit, and at
+        // the last uppercase letter of an acronym that is followed by a lowercase letter.
+        let prev_lower = i > 0 && (chars[i - 1].is_lowercase() || chars[i - 1].is_numeric());
+        let acronym_end = i > 0 && chars[i - 1].is_uppercase() && chars.get(i + 1).is_some_and(|next| next.is_lowercase());
+        if c.is_uppercase() && (prev_lower || acronym_end) && !current.is_empty() {
+            words.push(current);
+            current = String::new();
+        }
+        current.extend(c.to_lowercase());
+    }
+    if !current.is_empty() {
+        words.push(current);
+    }
+    words
+}
+
+/// Naming convention for option names and enum values.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RenameRule {
+    Kebab,
+    Snake,
+    Camel,
+    Lower,
+}
+
+impl RenameRule {
+    /// All rules, e.g. to name enum values for each of them.
+    pub const ALL: [RenameRule; 4] = [RenameRule::Kebab, RenameRule::Snake, RenameRule::Camel, RenameRule::Lower];
+
+    /// The value of a `rename_all` attribute that selects the rule.
+    pub fn name(self) -> &'static str {
+        match self {
+            RenameRule::Kebab => "kebab-case",
+            RenameRule::Snake => "snake_case",
+            RenameRule::Camel => "camelCase",
+            RenameRule::Lower => "lowercase",
+        }
+    }
+
+    /// Parses the value of a `rename_all` attribute.
+    pub fn from_name(name: &str) -> RenameRule {
+        match name {
+            "kebab-case" => RenameRule::Kebab,
+            "snake_case" => RenameRule::Snake,
+            "camelCase" => RenameRule::Camel,
+            "lowercase" => RenameRule::Lower,
+            _ => panic!("Unknown rename_all rule: {} (expected kebab-case, snake_case, camelCase or lowercase)", name),
+        }
+    }
+
+    /// Converts an identifier following the rule.
+    pub fn apply(self, ident: &str) -> String {
+        let words = split_words(ident);
+        match self {
+            RenameRule::Kebab => words.join("-"),
+            RenameRule::Snake => words.join("_"),
+            RenameRule::Lower => words.concat(),
+            RenameRule::Camel => words.iter().enumerate().map(|(i, word)| {
+                let mut chars = word.chars();
+                match chars.next() {
+                    Some(first) if i > 0 => first.to_uppercase().chain(chars).collect(),
+                    _ => word.clone(),
+                }
+            }).collect(),
+        }
+    }
+}
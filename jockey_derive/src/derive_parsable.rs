@@ -0,0 +1,126 @@
+use parser;
+
+use proc_macro2::TokenStream;
+
+pub fn derive_parsable(input: &syn::DeriveInput) -> TokenStream {
+    match parser::parse_data(input) {
+        parser::Data::Enum(data) => {
+            let enum_ident = &data.ident;
+
+            let mut match_arms = quote! {};
+            let mut format_arms = quote! {};
+            for variant in data.variants.iter() {
+                let ident = &variant.ident;
+                let (name, aliases) = if data.ignore_case {
+                    (variant.name.to_lowercase(), variant.aliases.iter().map(|alias| alias.to_lowercase()).collect())
+                }
+                else {
+                    (variant.name.clone(), variant.aliases.clone())
+                };
+                let display_name = &variant.name;
+                match_arms.extend(quote! {
+                    #name #(| #aliases)* => Ok(#enum_ident::#ident),
+                });
+                format_arms.extend(quote! {
+                    #enum_ident::#ident => #display_name.to_string(),
+                });
+            }
+
+            let names: Vec<&String> = data.variants.iter().map(|variant| &variant.name).collect();
+            let aliases: Vec<&String> = data.variants.iter().flat_map(|variant| variant.aliases.iter()).collect();
+            // Values are compared in lowercase if case is ignored.
+            let fold = if data.ignore_case { quote! { let value: &str = &value.to_lowercase(); } } else { quote! {} };
+            let message = format!("possible values are {}", names.iter().map(|name| name.as_str()).collect::<Vec<&str>>().join(", "));
+            let renamed_values = if data.renamed_values.is_empty() {
+                quote! {}
+            }
+            else {
+                let rules = data.renamed_values.iter().map(|&(rule, _)| rule.name());
+                let renamed = data.renamed_values.iter().map(|(_, names)| quote! { &[#(#names),*] });
+                quote! {
+                    fn renamed_values(rule: &str) -> &'static [&'static str] {
+                        match rule {
+                            #(#rules => #renamed,)*
+                            _ => <Self as jockey::ParsableValue>::possible_values(),
+                        }
+                    }
+                }
+            };
+
+            quote! {
+                impl jockey::ParsableValue for #enum_ident {
+                    fn parse_value(value: &str) -> std::result::Result<Self, String> {
+                        #fold
+                        match value {
+                            #match_arms
+                            _ => Err(#message.to_string()),
+                        }
+                    }
+
+                    fn possible_values() -> &'static [&'static str] {
+                        &[#(#names),*]
+                    }
+
+                    fn aliases() -> &'static [&'static str] {
+                        &[#(#aliases),*]
+                    }
+
+                    #renamed_values
+                }
+
+                impl jockey::FormattableValue for #enum_ident {
+                    fn format_value(&self) -> String {
+                        match *self {
+                            #format_arms
+                        }
+                    }
+                }
+
+                impl jockey::ParsableWithOption for #enum_ident {
+                    fn parse_arg<I>(iter: &mut std::iter::Peekable<I>, option: &String) -> jockey::ParseResult<Self>
+                        where I: Iterator<Item = (usize, String)>
+                    {
+                        jockey::parse_option_value(iter, option)
+                    }
+
+                    fn possible_values() -> &'static [&'static str] {
+                        <Self as jockey::ParsableValue>::possible_values()
+                    }
+
+                    fn aliases() -> &'static [&'static str] {
+                        <Self as jockey::ParsableValue>::aliases()
+                    }
+                }
+
+                impl jockey::FormattableWithOption for #enum_ident {
+                    fn format_arg(&self, option: &str) -> Vec<String> {
+                        jockey::format_option_value(option, self)
+                    }
+                }
+
+                impl jockey::ParsableWithPosition for #enum_ident {
+                    fn parse_arg<I>(iter: &mut std::iter::Peekable<I>, position: usize) -> jockey::ParseResult<Self>
+                        where I: Iterator<Item = (usize, String)>
+                    {
+                        jockey::parse_position_value(iter, position)
+                    }
+
+                    fn possible_values() -> &'static [&'static str] {
+                        <Self as jockey::ParsableValue>::possible_values()
+                    }
+
+                    fn aliases() -> &'static [&'static str] {
+                        <Self as jockey::ParsableValue>::aliases()
+                    }
+                }
+
+                impl jockey::FormattableWithPosition for #enum_ident {
+                    fn format_arg(&self) -> Option<String> {
+                        Some(jockey::FormattableValue::format_value(self))
+                    }
+                }
+            }
+        },
+        parser::Data::Struct(_) => panic!("Can only derive jockey::Parsable from enums."),
+    }
+}
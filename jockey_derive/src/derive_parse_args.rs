@@ -3,20 +3,22 @@ use parser;
 use proc_macro2::{TokenStream};
 use syn::{Ident, Type};
 
-fn get_parser_component(ident: &Ident, ty: &Type, parse_expression: TokenStream) -> TokenStream {
+/// Local variable holding the value parsed for a field until all arguments are parsed.
+fn get_parsed_ident(ident: &Ident) -> Ident {
+    Ident::new(&format!("parsed_{}", ident), ident.span())
+}
+
+fn get_parser_component(ident: &Ident, parse_expression: TokenStream, assign_statement: TokenStream) -> TokenStream {
     let span = ident.span();
+    let name = ident.to_string();
     quote_spanned!{span=>
         {
+            let start = iter.peek().map_or(argv.len(), |(index, _)| *index);
             let parse_result = #parse_expression;
-            match parse_result.blacklist {
-                Some(val) => {
-                    blacklist.insert(val.to_string());
-                },
-                None => {}
-            }
             match parse_result.parsed {
                 Some(Ok(val)) => {
-                    result.#ident = <#ty as jockey::ParsableWithOption>::assign(result.#ident, val);
+                    #assign_statement;
+                    taken.push((#name, start..iter.peek().map_or(argv.len(), |(index, _)| *index)));
                     continue;
                 },
                 Some(Err(err)) => return Err(err),
@@ -26,36 +28,542 @@ fn get_parser_component(ident: &Ident, ty: &Type, parse_expression: TokenStream)
     }
 }
 
-fn get_parser_component_option(ident: &Ident, ty: &Type, option: &String) -> TokenStream {
-    get_parser_component(ident, ty, quote!{
-        <#ty as jockey::ParsableWithOption>::parse_arg(&mut iter, &#option.to_string())
-    })
+fn get_assign_statement(ident: &Ident, ty: &Type) -> TokenStream {
+    let parsed = get_parsed_ident(ident);
+    quote!{
+        #parsed = Some(match #parsed.take() {
+            Some(lhs) => <#ty as jockey::ParsableWithOption>::assign(lhs, val),
+            None => val,
+        })
+    }
+}
+
+fn get_merge_statement(ident: &Ident, ty: &Type, option: &str, policy: &str) -> TokenStream {
+    let parsed = get_parsed_ident(ident);
+    let policy = get_policy_tokens(policy);
+    quote!{
+        #parsed = Some(match #parsed.take() {
+            Some(lhs) => <#ty as jockey::ParsableMap>::merge(lhs, val, #policy, #option)?,
+            None => val,
+        })
+    }
+}
+
+fn get_policy_tokens(policy: &str) -> TokenStream {
+    match policy {
+        "error" => quote!{ jockey::DuplicateKeys::Error },
+        "last_wins" => quote!{ jockey::DuplicateKeys::LastWins },
+        "collect" => quote!{ jockey::DuplicateKeys::Collect },
+        _ => panic!("Unknown duplicate_keys policy: {}", policy),
+    }
+}
+
+/// Closure that converts a single value of a field with custom conversion attributes.
+fn get_convert_expression(inner: &Type, conversion: &parser::Conversion) -> TokenStream {
+    let check_possible_values = if conversion.possible_values.is_empty() {
+        quote!{}
+    }
+    else {
+        let possible_values = &conversion.possible_values;
+        let message = format!("possible values are {}", possible_values.join(", "));
+        quote!{
+            if ![#(#possible_values),*].contains(&value) {
+                return Err(#message.to_string());
+            }
+        }
+    };
+    let check_range = match conversion.range {
+        Some(ref range) => {
+            let tokens: TokenStream = match range.parse() {
+                Ok(tokens) if range.contains("..") => tokens,
+                _ => panic!("Expected a range like \"1..=10\": {}", range),
+            };
+            let message = format!("must be in range {}", range);
+            quote!{
+                if !(#tokens).contains(&val) {
+                    return Err(#message.to_string());
+                }
+            }
+        },
+        None => quote!{},
+    };
+    let values = match conversion.rename_all {
+        Some(rule) => {
+            let rule = rule.name();
+            quote!{ <#inner as jockey::ParsableValue>::renamed_values(#rule) }
+        },
+        None => quote!{ <#inner as jockey::ParsableValue>::possible_values() },
+    };
+    let canonicalize = if !conversion.ignore_case {
+        quote!{}
+    }
+    else if !conversion.possible_values.is_empty() {
+        let possible_values = &conversion.possible_values;
+        quote!{ let value = jockey::canonical_value(value, &[#(#possible_values),*]); }
+    }
+    else if conversion.parse_with.is_none() {
+        quote!{
+            let value = jockey::canonical_value(value, #values);
+            let value = jockey::canonical_value(value, <#inner as jockey::ParsableValue>::aliases());
+        }
+    }
+    else {
+        quote!{}
+    };
+    let rename = if conversion.rename_all.is_some() {
+        quote!{ let value = jockey::translate_value(value, #values, <#inner as jockey::ParsableValue>::possible_values()); }
+    }
+    else {
+        quote!{}
+    };
+    let parse = match conversion.parse_with {
+        Some(ref path) => quote!{ #path(value).map_err(|err| err.to_string())? },
+        None => quote!{ <#inner as jockey::ParsableValue>::parse_value(value)? },
+    };
+    let validate = match conversion.validate_with {
+        Some(ref path) => quote!{ #path(&val).map_err(|err| err.to_string())?; },
+        None => quote!{},
+    };
+    quote!{
+        |value: &str| -> std::result::Result<#inner, String> {
+            #canonicalize
+            #rename
+            #check_possible_values
+            let val = #parse;
+            #check_range
+            #validate
+            Ok(val)
+        }
+    }
+}
+
+/// Fields with custom conversion bypass `ParsableWithOption` so they are assigned depending on
+/// their kind.
+fn get_custom_assign_statement(ident: &Ident, kind: &parser::Kind, delimited: bool) -> TokenStream {
+    let parsed = get_parsed_ident(ident);
+    match *kind {
+        parser::Kind::Plain => quote!{ #parsed = Some(val) },
+        parser::Kind::Option => quote!{ #parsed = Some(Some(val)) },
+        parser::Kind::Vec if delimited => quote!{ #parsed.get_or_insert_with(Vec::new).extend(val) },
+        parser::Kind::Vec => quote!{ #parsed.get_or_insert_with(Vec::new).push(val) },
+    }
+}
+
+/// Expression that converts the arguments `val` taken by a trailing field to its type.
+fn get_trailing_convert_expression(field: &parser::TrailingField) -> TokenStream {
+    if field.is_os_string() {
+        quote!{ val.into_iter().map(std::ffi::OsString::from).collect() }
+    }
+    else {
+        quote!{ val }
+    }
+}
+
+/// Field name along with the source of its value if it is not given, see `jockey::Parsed::new`.
+fn get_fallback_source(field: &parser::Field) -> TokenStream {
+    let name = get_field_ident(field).to_string();
+    let source = match *field {
+        parser::Field::Ordinary(parser::OrdinaryField { fallback: parser::Fallback::DefaultValue(_), .. }) |
+        parser::Field::Position(parser::PositionField { fallback: parser::Fallback::DefaultValue(_), .. }) => {
+            quote!{ jockey::Source::DefaultValue }
+        },
+        _ => quote!{ jockey::Source::Default },
+    };
+    quote!{ (#name, #source) }
+}
+
+/// Expression that applies the parsed value `val` of a field to the current value `lhs`.
+fn get_apply_expression(field: &parser::Field) -> TokenStream {
+    let name = get_field_name(field);
+    match *field {
+        parser::Field::Ordinary(ref field) => {
+            let ty = &field.ty;
+            let (kind, _) = parser::split_type(ty);
+            if field.conversion.is_custom() && kind == parser::Kind::Vec {
+                quote!{ { let mut lhs = lhs; lhs.extend(val); lhs } }
+            }
+            else if field.conversion.is_custom() || parser::split_optional_value_type(ty).is_some() {
+                quote!{ val }
+            }
+            else if let Some(ref policy) = field.duplicate_keys {
+                let policy = get_policy_tokens(policy);
+                quote!{ <#ty as jockey::ParsableMap>::merge(lhs, val, #policy, #name)? }
+            }
+            else {
+                quote!{ <#ty as jockey::ParsableWithOption>::assign(lhs, val) }
+            }
+        },
+        parser::Field::Position(_) => quote!{ val },
+        parser::Field::Unknown(ref field) => {
+            let ty = &field.ty;
+            quote!{ { let mut lhs = lhs; <#ty as std::iter::Extend<String>>::extend(&mut lhs, val); lhs } }
+        },
+        parser::Field::Trailing(ref field) => get_trailing_convert_expression(field),
+    }
+}
+
+/// Whether `update_from_args` takes the current value of a field to apply the parsed value with
+/// `ParsableWithOption::assign` or `ParsableMap::merge`. Other fields are assigned or extended in
+/// place, which needs no `Default` to take the value from.
+fn takes_current_value(field: &parser::Field, settings: &parser::StructSettings) -> bool {
+    match *field {
+        parser::Field::Ordinary(ref field) => {
+            let (kind, _) = parser::split_type(&field.ty);
+            !field.conversion.is_custom() && parser::split_optional_value_type(&field.ty).is_none() &&
+                (kind != parser::Kind::Plain || field.duplicate_keys.is_some() || (field.fallback == parser::Fallback::Default && !settings.default))
+        },
+        _ => false,
+    }
+}
+
+/// Statement that fails before anything is applied in `update_from_args` if a key given for a
+/// field with `duplicate_keys = "error"` is already in the current value.
+fn get_update_check_statement(field: &parser::Field) -> TokenStream {
+    match *field {
+        parser::Field::Ordinary(ref ordinary) if ordinary.duplicate_keys.as_deref() == Some("error") => {
+            let ident = &ordinary.ident;
+            let parsed = get_parsed_ident(ident);
+            let option = get_field_name(field);
+            quote!{
+                if let Some(ref val) = #parsed {
+                    jockey::check_duplicate_keys(&self.#ident, val, #option)?;
+                }
+            }
+        },
+        _ => quote!{},
+    }
+}
+
+/// Statement that applies the parsed value of a field to `self` in `update_from_args`.
+fn get_update_statement(field: &parser::Field, settings: &parser::StructSettings) -> TokenStream {
+    let ident = get_field_ident(field);
+    let parsed = get_parsed_ident(ident);
+    let apply = if takes_current_value(field, settings) {
+        let expression = get_apply_expression(field);
+        quote!{ let lhs = std::mem::take(&mut self.#ident); self.#ident = #expression; }
+    }
+    else {
+        match *field {
+            parser::Field::Ordinary(ref ordinary) if ordinary.conversion.is_custom() && parser::split_type(&ordinary.ty).0 == parser::Kind::Vec => {
+                quote!{ self.#ident.extend(val); }
+            },
+            parser::Field::Ordinary(_) | parser::Field::Position(_) => quote!{ self.#ident = val; },
+            parser::Field::Unknown(ref unknown) => {
+                let ty = &unknown.ty;
+                quote!{ <#ty as std::iter::Extend<String>>::extend(&mut self.#ident, val); }
+            },
+            parser::Field::Trailing(ref trailing) => {
+                let convert = get_trailing_convert_expression(trailing);
+                quote!{ self.#ident = #convert; }
+            },
+        }
+    };
+    match *field {
+        parser::Field::Unknown(_) => quote!{ { let val = #parsed; #apply } },
+        _ => quote!{ if let Some(val) = #parsed { #apply } },
+    }
+}
+
+/// Name of a field in error messages.
+fn get_field_name(field: &parser::Field) -> String {
+    match *field {
+        parser::Field::Ordinary(ref field) => field.long.as_ref().or(field.short.as_ref()).unwrap().clone(),
+        parser::Field::Position(ref field) => format!("argument {}", field.position),
+        parser::Field::Unknown(ref field) => field.ident.to_string(),
+        parser::Field::Trailing(ref field) => field.ident.to_string(),
+    }
+}
+
+fn get_field_ident(field: &parser::Field) -> &Ident {
+    match *field {
+        parser::Field::Ordinary(ref field) => &field.ident,
+        parser::Field::Position(ref field) => &field.ident,
+        parser::Field::Unknown(ref field) => &field.ident,
+        parser::Field::Trailing(ref field) => &field.ident,
+    }
+}
+
+/// Expression that converts the `default_value` of a field.
+fn get_default_value_expression(field: &parser::Field, value: &str) -> TokenStream {
+    let name = get_field_name(field);
+    let (ty, conversion) = match *field {
+        parser::Field::Ordinary(ref field) => (&field.ty, &field.conversion),
+        parser::Field::Position(ref field) => (&field.ty, &field.conversion),
+        parser::Field::Unknown(_) | parser::Field::Trailing(_) => unreachable!(),
+    };
+    let (kind, inner) = parser::split_type(ty);
+    if let Some(innermost) = parser::split_optional_value_type(ty) {
+        let convert = get_convert_expression(&innermost, conversion);
+        return quote!{ Some(Some(jockey::parse_default_with(#name, #value, #convert)?)) };
+    }
+    let convert = get_convert_expression(&inner, conversion);
+    match kind {
+        parser::Kind::Plain => quote!{ jockey::parse_default_with(#name, #value, #convert)? },
+        parser::Kind::Option => quote!{ Some(jockey::parse_default_with(#name, #value, #convert)?) },
+        parser::Kind::Vec => match conversion.delimiter {
+            Some(delimiter) => quote!{ jockey::parse_default_delimited_with(#name, #value, #delimiter, #convert)? },
+            None => quote!{ vec![jockey::parse_default_with(#name, #value, #convert)?] },
+        },
+    }
+}
+
+/// Expression that builds the value of a field in `parse_args`.
+fn get_build_expression(field: &parser::Field, settings: &parser::StructSettings) -> TokenStream {
+    let ident = get_field_ident(field);
+    let parsed = get_parsed_ident(ident);
+    let (ty, fallback) = match *field {
+        parser::Field::Ordinary(ref field) => (&field.ty, &field.fallback),
+        parser::Field::Position(ref field) => (&field.ty, &field.fallback),
+        parser::Field::Unknown(ref field) => (&field.ty, &parser::Fallback::Default),
+        parser::Field::Trailing(ref field) => (&field.ty, &parser::Fallback::Default),
+    };
+    let missing = match *fallback {
+        parser::Fallback::Default if settings.default => quote!{ default.#ident },
+        parser::Fallback::Default => {
+            let span = ident.span();
+            quote_spanned!{span=> <#ty as Default>::default() }
+        },
+        parser::Fallback::Required => {
+            let name = get_field_name(field);
+            quote!{ return Err(jockey::Error::MissingArgument(#name.to_string())) }
+        },
+        parser::Fallback::DefaultValue(ref value) => get_default_value_expression(field, value),
+    };
+    // Given values replace the defaults, also of fields holding several values like Vec.
+    let given = if let parser::Field::Trailing(ref field) = *field {
+        get_trailing_convert_expression(field)
+    }
+    else {
+        quote!{ val }
+    };
+    match *field {
+        parser::Field::Unknown(_) => {
+            let apply = get_apply_expression(field);
+            let span = ident.span();
+            let empty = quote_spanned!{span=> <#ty as Default>::default() };
+            quote!{
+                if #parsed.is_empty() {
+                    #missing
+                }
+                else {
+                    let lhs = #empty;
+                    let val = #parsed;
+                    #apply
+                }
+            }
+        },
+        _ => quote!{
+            match #parsed {
+                Some(val) => #given,
+                None => #missing,
+            }
+        },
+    }
+}
+
+/// Declaration of the local variable that holds the parsed value of a field.
+fn get_parsed_declaration(field: &parser::Field) -> TokenStream {
+    match *field {
+        parser::Field::Ordinary(parser::OrdinaryField { ref ident, ref ty, .. }) |
+        parser::Field::Position(parser::PositionField { ref ident, ref ty, .. }) => {
+            let parsed = get_parsed_ident(ident);
+            quote!{ let mut #parsed: Option<#ty> = None; }
+        },
+        parser::Field::Unknown(ref field) => {
+            let parsed = get_parsed_ident(&field.ident);
+            quote!{ let mut #parsed: Vec<String> = Vec::new(); }
+        },
+        parser::Field::Trailing(ref field) => {
+            let parsed = get_parsed_ident(&field.ident);
+            quote!{ let mut #parsed: Option<Vec<String>> = None; }
+        },
+    }
+}
+
+fn get_parser_component_option(field: &parser::OrdinaryField, option: &str) -> TokenStream {
+    let ident = &field.ident;
+    let ty = &field.ty;
+    let (kind, inner) = parser::split_type(ty);
+
+    let nested = parser::split_optional_value_type(ty);
+
+    if field.conversion.is_custom() || nested.is_some() {
+        if field.duplicate_keys.is_some() {
+            panic!("duplicate_keys cannot be combined with custom conversions");
+        }
+        let convert = get_convert_expression(nested.as_ref().unwrap_or(&inner), &field.conversion);
+        let parse_expression = if nested.is_some() || field.conversion.default_missing_value.is_some() {
+            if field.conversion.delimiter.is_some() {
+                panic!("value_delimiter cannot be used on options with optional values");
+            }
+            let wrap = if nested.is_some() { quote!{ .map(Some) } } else { quote!{} };
+            let missing = match field.conversion.default_missing_value {
+                Some(ref default) => quote!{ (#convert)(#default)#wrap },
+                None => quote!{ Ok(None) },
+            };
+            let parse = quote!{
+                jockey::parse_optional_option_with(&mut iter, #option, |value: Option<&str>| match value {
+                    Some(value) => (#convert)(value)#wrap,
+                    None => #missing,
+                })
+            };
+            match kind {
+                parser::Kind::Vec => quote!{ jockey::ParseResult { blacklist: None, ..#parse } },
+                _ => parse,
+            }
+        }
+        else {
+            match field.conversion.delimiter {
+                Some(delimiter) => {
+                    if kind != parser::Kind::Vec {
+                        panic!("value_delimiter can only be used on Vec fields");
+                    }
+                    quote!{ jockey::parse_delimited_with(&mut iter, #option, #delimiter, #convert) }
+                },
+                None if kind == parser::Kind::Vec => quote!{
+                    jockey::ParseResult { blacklist: None, ..jockey::parse_option_with(&mut iter, #option, #convert) }
+                },
+                None => quote!{ jockey::parse_option_with(&mut iter, #option, #convert) },
+            }
+        };
+        let assign_statement = get_custom_assign_statement(ident, &kind, field.conversion.delimiter.is_some());
+        return get_parser_component(ident, parse_expression, get_duplicate_statement(field, assign_statement));
+    }
+
+    let parse_expression = match field.validate_with {
+        Some(ref path) => {
+            let value_ty = if kind == parser::Kind::Plain { ty } else { &inner };
+            let validated = quote!{
+                jockey::validate_option_with(<#value_ty as jockey::ParsableWithOption>::parse_arg(&mut iter, &#option.to_string()), #option, #path)
+            };
+            match kind {
+                parser::Kind::Plain => validated,
+                parser::Kind::Option => quote!{ #validated.map(Some) },
+                parser::Kind::Vec => quote!{ jockey::ParseResult { blacklist: None, ..#validated.map(|val| vec![val]) } },
+            }
+        },
+        None => quote!{
+            <#ty as jockey::ParsableWithOption>::parse_arg(&mut iter, &#option.to_string())
+        },
+    };
+    let assign_statement = match field.duplicate_keys {
+        Some(ref policy) => get_merge_statement(ident, ty, option, policy),
+        None => get_assign_statement(ident, ty),
+    };
+    get_parser_component(ident, parse_expression, get_duplicate_statement(field, assign_statement))
+}
+
+/// Applies the duplicate_options policy of a field before its value is assigned, see
+/// `jockey::check_duplicate_option`. Options are tracked by field so that all names of a field count.
+fn get_duplicate_statement(field: &parser::OrdinaryField, assign_statement: TokenStream) -> TokenStream {
+    let name = field.ident.to_string();
+    let policy = match field.duplicate_options {
+        parser::DuplicateOptions::Error => quote!{ jockey::DuplicateOptions::Error },
+        parser::DuplicateOptions::FirstWins => quote!{ jockey::DuplicateOptions::FirstWins },
+        parser::DuplicateOptions::LastWins => return assign_statement,
+    };
+    quote!{
+        if jockey::check_duplicate_option(&mut given, #name, parse_result.blacklist.as_ref(), #policy)? {
+            #assign_statement
+        }
+    }
 }
 
-fn get_parser_component_position(ident: &Ident, ty: &Type, position: u64) -> TokenStream {
-    get_parser_component(ident, ty, quote!{
-        <#ty as jockey::ParsableWithPosition>::parse_arg(&mut iter, #position as usize);
-    })
+/// Fails if a value given after the option, rather than attached to it, looks like an option. Negative
+/// numbers are taken by numeric values.
+fn get_hyphen_values_check(field: &parser::OrdinaryField, option: &str, prefixes: &[&str], options: &[String]) -> TokenStream {
+    let optional_value = parser::split_optional_value_type(&field.ty).is_some() || field.conversion.default_missing_value.is_some();
+    let value_types = parser::split_value_types(&field.ty);
+    if field.allow_hyphen_values || optional_value || value_types.is_empty() {
+        return quote!{};
+    }
+    let count = value_types.len();
+    let negative_numbers = value_types.iter().map(parser::is_numeric);
+    quote!{
+        if let Some((index, arg)) = iter.peek() {
+            if arg == #option {
+                let negative_numbers = [#(#negative_numbers),*];
+                jockey::check_option_values(#option, &argv[index + 1..], #count, |found, value| {
+                    jockey::is_value_like(value, &[#(#prefixes),*], negative_numbers[found], &[#(#options),*])
+                })?;
+            }
+        }
+    }
+}
+
+fn get_parser_component_position(field: &parser::PositionField, prefixes: &[&str], options: &[String]) -> TokenStream {
+    let ident = &field.ident;
+    let ty = &field.ty;
+    let position = field.position;
+    let (kind, inner) = parser::split_type(ty);
+    let component = if field.conversion.is_custom() {
+        if kind == parser::Kind::Vec {
+            panic!("Positional arguments cannot be Vec fields");
+        }
+        let convert = get_convert_expression(&inner, &field.conversion);
+        get_parser_component(ident, quote!{
+            jockey::parse_position_with(&mut iter, #position as usize, #convert)
+        }, get_custom_assign_statement(ident, &kind, false))
+    }
+    else {
+        let parsed = get_parsed_ident(ident);
+        get_parser_component(ident, quote!{
+            <#ty as jockey::ParsableWithPosition>::parse_arg(&mut iter, #position as usize)
+        }, quote!{ #parsed = Some(val) })
+    };
+    if field.allow_hyphen_values {
+        return component;
+    }
+
+    // Arguments that look like options are left to the options and the unknown arguments, unless
+    // options are no longer matched.
+    let negative_number = parser::is_numeric(&inner);
+    quote!{
+        let value_like = match iter.peek() {
+            Some((_, arg)) => options_done || jockey::is_value_like(arg, &[#(#prefixes),*], #negative_number, &[#(#options),*]),
+            None => false,
+        };
+        if value_like {
+            #component
+        }
+    }
 }
 
 pub fn derive_parse_args(input: &syn::DeriveInput) -> proc_macro2::TokenStream {
     match parser::parse_data(input) {
         parser::Data::Struct(data) => {
+            let declarations: TokenStream = data.fields.iter().map(get_parsed_declaration).collect();
+            let update_check_statements = data.fields.iter().map(get_update_check_statement);
+            let update_statements = data.fields.iter().map(|field| get_update_statement(field, &data.settings));
+            let field_idents = data.fields.iter().map(get_field_ident);
+            let build_expressions = data.fields.iter().map(|field| get_build_expression(field, &data.settings));
+            let fallback_sources = data.fields.iter().map(get_fallback_source);
+            let validate = match data.settings.validate {
+                Some(ref path) => quote! {
+                    fn validate(&self) -> jockey::Result<()> {
+                        #path(self).map_err(Into::into)
+                    }
+                },
+                None => quote! {},
+            };
+            let default_declaration = if data.settings.default {
+                quote!{ let default = <Self as Default>::default(); }
+            }
+            else {
+                quote!{}
+            };
+
+            let prefixes = [data.settings.long_prefix(), data.settings.short_prefix()];
+            let all_options = data.all_options();
             let mut parser_components = quote! {};
             let mut unknown_args_field: Option<parser::UnknownField> = None;
-            for field in data.fields { match field {
+            let mut trailing_field: Option<parser::TrailingField> = None;
+            for field in data.fields.iter().cloned() { match field {
                 parser::Field::Ordinary(field) => {
-                    match field.long {
-                        Some(option) => {
-                            parser_components.extend(get_parser_component_option(&field.ident, &field.ty, &option));
-                        },
-                        None => {},
-                    }
-                    match field.short {
-                        Some(option) => {
-                            parser_components.extend(get_parser_component_option(&field.ident, &field.ty, &option));
-                        },
-                        None => {},
+                    for option in field.long.iter().chain(field.short.iter()) {
+                        let check = get_hyphen_values_check(&field, option, &prefixes, &all_options);
+                        let component = get_parser_component_option(&field, option);
+                        parser_components.extend(quote! { if !options_done { #check #component } });
                     }
                 },
                 parser::Field::Unknown(field) => {
@@ -66,18 +574,106 @@ pub fn derive_parse_args(input: &syn::DeriveInput) -> proc_macro2::TokenStream {
                 },
 
                 parser::Field::Position(field) => {
-                    parser_components.extend(get_parser_component_position(&field.ident, &field.ty, field.position));
-                }
+                    parser_components.extend(get_parser_component_position(&field, &prefixes, &all_options));
+                },
+                parser::Field::Trailing(field) => {
+                    if trailing_field.is_some() {
+                        panic!("Only one trailing_var_arg field may be defined");
+                    }
+                    trailing_field = Some(field);
+                },
             }}
 
+            let (help_short, help_long) = data.help_options();
+            let help_options: Vec<String> = help_short.into_iter().chain(help_long).collect();
+            let (version_short, version_long) = data.version_options();
+            let version_options: Vec<String> = version_short.into_iter().chain(version_long).collect();
+            let builtin_component = if help_options.is_empty() && version_options.is_empty() {
+                quote! {}
+            }
+            else {
+                quote! {
+                    if let Some((_, arg)) = iter.peek() {
+                        jockey::check_builtin_option(arg, &[#(#help_options),*], &[#(#version_options),*])?;
+                    }
+                }
+            };
+
+            let ignore_case_options = data.ignore_case_options();
+            let ignore_case_component = if ignore_case_options.is_empty() {
+                quote! {}
+            }
+            else {
+                let prefix = data.settings.long_prefix();
+                quote! {
+                    jockey::rewrite_next_arg(&mut iter, |arg| Ok(jockey::match_ignore_case(arg, #prefix, &[#(#ignore_case_options),*])))?;
+                }
+            };
+
+            let abbrev_component = if data.settings.allow_abbrev {
+                let options = data.all_options();
+                let prefix = data.settings.long_prefix();
+                quote! {
+                    jockey::rewrite_next_arg(&mut iter, |arg| jockey::expand_abbreviation(arg, #prefix, &[#(#options),*]))?;
+                }
+            }
+            else {
+                quote! {}
+            };
+
+            let glued_value_options = data.glued_value_options();
+            let glued_value_component = if glued_value_options.is_empty() {
+                quote! {}
+            }
+            else {
+                let options = data.all_options();
+                quote! {
+                    jockey::rewrite_next_arg(&mut iter, |arg| Ok(jockey::split_glued_value(arg, &[#(#options),*], &[#(#glued_value_options),*])))?;
+                }
+            };
+
+            // The rest of the arguments is taken verbatim after "--" or from the position on.
+            let trailing_component = match trailing_field {
+                Some(field) => {
+                    let parsed = get_parsed_ident(&field.ident);
+                    let name = field.ident.to_string();
+                    let from_position = match field.position {
+                        Some(position) => {
+                            let position = position as usize;
+                            quote! { *index >= #position }
+                        },
+                        None => quote! { false },
+                    };
+                    quote! {
+                        let (from_position, marker) = match iter.peek() {
+                            Some((index, arg)) => (#from_position, arg == "--"),
+                            None => (false, false),
+                        };
+                        if from_position || marker {
+                            if !from_position {
+                                iter.next();
+                            }
+                            taken.push((#name, iter.peek().map_or(argv.len(), |(index, _)| *index)..argv.len()));
+                            #parsed.get_or_insert_with(Vec::new).extend(iter.by_ref().map(|(_, arg)| arg));
+                            break;
+                        }
+                    }
+                },
+                None => quote! {},
+            };
+
             let unknown_args_component = match unknown_args_field {
                 Some(field) => {
                     let ident = &field.ident;
-                    let ty = &field.ty;
+                    let parsed = get_parsed_ident(ident);
+                    let name = ident.to_string();
                     let span = ident.span();
                     quote_spanned! { span =>
                         match iter.next() {
-                            Some((_, value)) => <#ty as std::iter::Extend<String>>::extend(&mut result.#ident, std::iter::once(value)),
+                            Some((index, value)) => {
+                                #parsed.push(value);
+                                taken.push((#name, index..index + 1));
+                            },
                             None => {},
                         }
                     }
@@ -87,38 +683,104 @@ pub fn derive_parse_args(input: &syn::DeriveInput) -> proc_macro2::TokenStream {
                 },
             };
 
-            let struct_ident = &input.ident;
-            quote! {
-                fn parse_args<I> (args: I) -> jockey::Result<#struct_ident> where I : Iterator<Item = String> {
-                    let mut result = <#struct_ident as Default>::default();
-                    let mut blacklist: std::collections::HashSet<String> = std::collections::HashSet::new();
-                    let mut iter = args.enumerate().peekable();
-
-                    // Skip first argument which is the executable path.
-                    iter.next();
-
-                    loop {
-                        match iter.peek() {
-                            Some((_, arg)) => {
-                                if blacklist.contains(arg) {
-                                    return Err(jockey::Error::DuplicateOption(arg.to_string()));
-                                }
-                            },
-                            None => {
-                                break;
-                            },
-                        }
-                        if iter.peek().is_none() { break; }
+            let stop_at_first_positional = if data.settings.posixly_correct {
+                quote! { jockey::posixly_correct() }
+            }
+            else {
+                let stop = data.settings.stop_at_first_positional;
+                quote! { #stop }
+            };
+            // Fields whose options were given, see get_duplicate_statement.
+            let tracks_duplicates = data.fields.iter().any(|field| match *field {
+                parser::Field::Ordinary(ref field) => field.duplicate_options != parser::DuplicateOptions::LastWins,
+                _ => false,
+            });
+            let given_declaration = if tracks_duplicates {
+                quote!{ let mut given: std::collections::HashSet<&str> = std::collections::HashSet::new(); }
+            }
+            else {
+                quote!{}
+            };
+
+            // Collected so that the values after an option can be checked before it is parsed.
+            let collect_args = quote! {
+                let argv: Vec<String> = args.collect();
+                let mut iter = argv.iter().cloned().enumerate().peekable();
+            };
+
+            let parse_loop = quote! {
+                // Field names and argument indices of every occurrence, see jockey::Parsed.
+                let mut taken: Vec<(&str, std::ops::Range<usize>)> = Vec::new();
+                #given_declaration
+                #collect_args
+                let stop_at_first_positional = #stop_at_first_positional;
+                // Set once options are no longer matched, see stop_at_first_positional.
+                let mut options_done = false;
+
+                // Skip first argument which is the executable path.
+                iter.next();
+
+                loop {
+                    #trailing_component
 
-                        #parser_components
+                    if !options_done {
+                        #ignore_case_component
+
+                        #abbrev_component
+
+                        #glued_value_component
+                    }
+
+                    match iter.peek() {
+                        Some((_, arg)) => {
+                            if stop_at_first_positional && !options_done && !jockey::is_option_like(arg, &[#(#prefixes),*]) {
+                                options_done = true;
+                            }
+                        },
+                        None => {
+                            break;
+                        },
+                    }
+                    if iter.peek().is_none() { break; }
 
-                        #unknown_args_component
+                    if !options_done {
+                        #builtin_component
                     }
 
-                    Ok(result)
+                    #parser_components
+
+                    #unknown_args_component
+                }
+            };
+
+            quote! {
+                fn parse_args<I> (args: I) -> jockey::Result<Self> where I : Iterator<Item = String> {
+                    Self::parse_args_with_meta(args).map(jockey::Parsed::into_inner)
+                }
+
+                fn parse_args_with_meta<I> (args: I) -> jockey::Result<jockey::Parsed<Self>> where I : Iterator<Item = String> {
+                    #declarations
+                    #parse_loop
+                    #default_declaration
+                    let result = Self {
+                        #(#field_idents: #build_expressions,)*
+                    };
+                    result.validate()?;
+                    Ok(jockey::Parsed::new(result, &[#(#fallback_sources),*], &taken))
+                }
+
+                fn update_from_args<I> (&mut self, args: I) -> jockey::Result<()> where I : Iterator<Item = String> {
+                    #declarations
+                    #parse_loop
+                    #(#update_check_statements)*
+                    #(#update_statements)*
+                    Ok(())
                 }
+
+                #validate
             }
         },
-    }.into()
+        parser::Data::Enum(_) => panic!("Can only derive jockey::Arguments from structs."),
+    }
 }
 
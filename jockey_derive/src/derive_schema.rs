@@ -0,0 +1,234 @@
+use parser;
+
+use proc_macro2::TokenStream;
+
+/// Placeholder for the values of a field, e.g. "FILE" for a field named `file`.
+fn get_value_name(ident: &syn::Ident) -> String {
+    ident.to_string().to_uppercase()
+}
+
+/// Name of the type as written, e.g. "Vec<String>".
+fn get_type_name(ty: &syn::Type) -> String {
+    quote!(#ty).to_string().replace(' ', "")
+}
+
+fn get_option_tokens(value: &Option<String>) -> TokenStream {
+    match *value {
+        Some(ref value) => quote!{ Some(String::from(#value)) },
+        None => quote!{ None },
+    }
+}
+
+fn is_map(ty: &syn::Type) -> bool {
+    match *ty {
+        syn::Type::Path(ref type_path) => match type_path.path.segments.iter().last() {
+            Some(segment) => segment.ident == "HashMap" || segment.ident == "BTreeMap",
+            None => false,
+        },
+        _ => false,
+    }
+}
+
+/// The values a field accepts: the possible_values of the field, or else those of its type
+/// followed by their aliases. `parsable` is the trait the field is parsed with unless it converts
+/// its values itself.
+fn get_possible_values(ty: &syn::Type, conversion: &parser::Conversion, parsable: TokenStream) -> TokenStream {
+    if !conversion.possible_values.is_empty() {
+        let possible_values = &conversion.possible_values;
+        return quote!{ vec![#(String::from(#possible_values)),*] };
+    }
+    if conversion.parse_with.is_some() || !parser::takes_single_value(ty) {
+        return quote!{ vec![] };
+    }
+    if !conversion.is_custom() && parser::split_optional_value_type(ty).is_none() {
+        return quote!{
+            <#ty as #parsable>::possible_values().iter().chain(<#ty as #parsable>::aliases()).map(|value| value.to_string()).collect()
+        };
+    }
+    let value_ty = parser::split_optional_value_type(ty).unwrap_or_else(|| parser::split_type(ty).1);
+    let values = match conversion.rename_all {
+        Some(rule) => {
+            let rule = rule.name();
+            quote!{ <#value_ty as jockey::ParsableValue>::renamed_values(#rule) }
+        },
+        None => quote!{ <#value_ty as jockey::ParsableValue>::possible_values() },
+    };
+    quote!{
+        #values.iter().chain(<#value_ty as jockey::ParsableValue>::aliases()).map(|value| value.to_string()).collect()
+    }
+}
+
+/// The placeholders of the values an option takes, e.g. "SIZE" twice for a pair.
+fn get_value_names(field: &parser::OrdinaryField) -> Vec<String> {
+    let name = get_value_name(&field.ident);
+    let (kind, inner) = parser::split_type(&field.ty);
+    if is_map(if kind == parser::Kind::Plain { &field.ty } else { &inner }) {
+        return vec![String::from("KEY=VALUE")];
+    }
+    parser::split_value_types(&field.ty).iter().map(|_| name.clone()).collect()
+}
+
+fn get_option_schema(field: &parser::OrdinaryField) -> TokenStream {
+    let long = get_option_tokens(&field.long);
+    let short = get_option_tokens(&field.short);
+    let optional_value = parser::split_optional_value_type(&field.ty).is_some() || field.conversion.default_missing_value.is_some();
+    let value_names = if optional_value { vec![get_value_name(&field.ident)] } else { get_value_names(field) };
+    let type_name = get_type_name(&field.ty);
+    let (kind, inner) = parser::split_type(&field.ty);
+    let multiple = kind == parser::Kind::Vec || is_map(&inner);
+    let required = field.fallback == parser::Fallback::Required;
+    let default_value = match field.fallback {
+        parser::Fallback::DefaultValue(ref value) => quote!{ Some(String::from(#value)) },
+        _ => quote!{ None },
+    };
+    let possible_values = get_possible_values(&field.ty, &field.conversion, quote!{ jockey::ParsableWithOption });
+    let range = get_option_tokens(&field.conversion.range);
+    let help = field.help.clone().unwrap_or_default();
+    quote!{
+        jockey::OptionSchema {
+            long: #long,
+            short: #short,
+            value_names: vec![#(String::from(#value_names)),*],
+            optional_value: #optional_value,
+            type_name: String::from(#type_name),
+            multiple: #multiple,
+            required: #required,
+            default_value: #default_value,
+            possible_values: #possible_values,
+            range: #range,
+            help: String::from(#help),
+        }
+    }
+}
+
+fn get_builtin_option_schema(names: &(Option<String>, Option<String>), help: &str) -> TokenStream {
+    let short = get_option_tokens(&names.0);
+    let long = get_option_tokens(&names.1);
+    quote!{
+        jockey::OptionSchema {
+            long: #long,
+            short: #short,
+            type_name: String::from("bool"),
+            help: String::from(#help),
+            ..jockey::OptionSchema::default()
+        }
+    }
+}
+
+fn get_argument_schema(field: &parser::PositionField) -> TokenStream {
+    let name = get_value_name(&field.ident);
+    let position = field.position as usize;
+    let type_name = get_type_name(&field.ty);
+    let required = field.fallback == parser::Fallback::Required;
+    let default_value = match field.fallback {
+        parser::Fallback::DefaultValue(ref value) => quote!{ Some(String::from(#value)) },
+        _ => quote!{ None },
+    };
+    let possible_values = get_possible_values(&field.ty, &field.conversion, quote!{ jockey::ParsableWithPosition });
+    let range = get_option_tokens(&field.conversion.range);
+    let help = field.help.clone().unwrap_or_default();
+    quote!{
+        jockey::ArgumentSchema {
+            name: String::from(#name),
+            position: Some(#position),
+            type_name: String::from(#type_name),
+            required: #required,
+            default_value: #default_value,
+            possible_values: #possible_values,
+            range: #range,
+            trailing: false,
+            help: String::from(#help),
+        }
+    }
+}
+
+fn get_unknown_schema(field: &parser::UnknownField) -> TokenStream {
+    let name = get_value_name(&field.ident);
+    let type_name = get_type_name(&field.ty);
+    let help = field.help.clone().unwrap_or_default();
+    quote!{
+        jockey::ArgumentSchema {
+            name: String::from(#name),
+            position: None,
+            type_name: String::from(#type_name),
+            help: String::from(#help),
+            ..jockey::ArgumentSchema::default()
+        }
+    }
+}
+
+fn get_trailing_schema(field: &parser::TrailingField) -> TokenStream {
+    let name = get_value_name(&field.ident);
+    let position = match field.position {
+        Some(position) => {
+            let position = position as usize;
+            quote!{ Some(#position) }
+        },
+        None => quote!{ None },
+    };
+    let type_name = get_type_name(&field.ty);
+    let help = field.help.clone().unwrap_or_default();
+    quote!{
+        jockey::ArgumentSchema {
+            name: String::from(#name),
+            position: #position,
+            type_name: String::from(#type_name),
+            trailing: true,
+            help: String::from(#help),
+            ..jockey::ArgumentSchema::default()
+        }
+    }
+}
+
+pub fn derive_schema(input: &syn::DeriveInput) -> TokenStream {
+    let data = match parser::parse_data(input) {
+        parser::Data::Struct(data) => data,
+        parser::Data::Enum(_) => panic!("Can only derive jockey::Arguments from structs."),
+    };
+
+    let mut positions: Vec<&parser::PositionField> = Vec::new();
+    let mut options: Vec<TokenStream> = Vec::new();
+    let mut unknown_args = None;
+    let mut trailing = None;
+    for field in data.fields.iter() {
+        match *field {
+            parser::Field::Ordinary(ref field) => options.push(get_option_schema(field)),
+            parser::Field::Position(ref field) => positions.push(field),
+            parser::Field::Unknown(ref field) => unknown_args = Some(field),
+            parser::Field::Trailing(ref field) => trailing = Some(field),
+        }
+    }
+
+    for &(ref names, help) in [(data.help_options(), "Print help"), (data.version_options(), "Print version")].iter() {
+        if names.0.is_some() || names.1.is_some() {
+            options.push(get_builtin_option_schema(names, help));
+        }
+    }
+
+    positions.sort_by_key(|field| field.position);
+    let mut arguments: Vec<TokenStream> = positions.into_iter().map(get_argument_schema).collect();
+    if let Some(field) = unknown_args {
+        arguments.push(get_unknown_schema(field));
+    }
+    if let Some(field) = trailing {
+        arguments.push(get_trailing_schema(field));
+    }
+
+    let name = get_option_tokens(&data.settings.name);
+    let version = match data.settings.version {
+        Some(ref version) => quote! { #version },
+        None => quote! { env!("CARGO_PKG_VERSION") },
+    };
+    let about = get_option_tokens(&data.settings.about);
+    quote! {
+        fn schema() -> jockey::Schema {
+            jockey::Schema {
+                name: #name,
+                version: Some(String::from(#version)),
+                about: #about,
+                options: vec![#(#options),*],
+                arguments: vec![#(#arguments),*],
+            }
+        }
+    }
+}
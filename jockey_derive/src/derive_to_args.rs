@@ -0,0 +1,172 @@
+use parser;
+
+use proc_macro2::{TokenStream};
+use syn::{Type};
+
+/// Closure that formats a single value of a field with custom conversion attributes.
+fn get_format_expression(inner: &Type, conversion: &parser::Conversion) -> TokenStream {
+    match conversion.format_with {
+        Some(ref path) => quote!{ |val: &#inner| -> String { #path(val) } },
+        None if conversion.parse_with.is_none() => match conversion.rename_all {
+            Some(rule) => {
+                let rule = rule.name();
+                quote!{ |val: &#inner| -> String {
+                    let value = <#inner as jockey::FormattableValue>::format_value(val);
+                    jockey::translate_value(&value, <#inner as jockey::ParsableValue>::possible_values(), <#inner as jockey::ParsableValue>::renamed_values(#rule)).to_string()
+                } }
+            },
+            None => quote!{ |val: &#inner| -> String { <#inner as jockey::FormattableValue>::format_value(val) } },
+        },
+        None => quote!{ |val: &#inner| -> String { val.to_string() } },
+    }
+}
+
+fn get_format_component_option(field: &parser::OrdinaryField) -> TokenStream {
+    let ident = &field.ident;
+    let ty = &field.ty;
+    let option = field.long.as_ref().or(field.short.as_ref()).unwrap();
+    let (kind, inner) = parser::split_type(ty);
+    let nested = parser::split_optional_value_type(ty);
+
+    if let Some(ref innermost) = nested {
+        let format = get_format_expression(innermost, &field.conversion);
+        return quote!{
+            match self.#ident {
+                Some(Some(ref val)) => args.push(format!("{}={}", #option, (#format)(val))),
+                Some(None) => args.push(#option.to_string()),
+                None => {},
+            }
+        };
+    }
+
+    if field.conversion.is_custom() {
+        let format = get_format_expression(&inner, &field.conversion);
+        if let Some(delimiter) = field.conversion.delimiter {
+            return quote!{
+                if !self.#ident.is_empty() {
+                    let pieces: Vec<String> = self.#ident.iter().map(#format).collect();
+                    args.push(format!("{}={}", #option, jockey::join_escaped(&pieces, #delimiter)));
+                }
+            };
+        }
+        let values = match kind {
+            parser::Kind::Plain => quote!{ std::iter::once(&self.#ident) },
+            parser::Kind::Option | parser::Kind::Vec => quote!{ self.#ident.iter() },
+        };
+        return quote!{
+            for val in #values {
+                args.push(format!("{}={}", #option, (#format)(val)));
+            }
+        };
+    }
+
+    quote!{
+        args.extend(<#ty as jockey::FormattableWithOption>::format_arg(&self.#ident, #option));
+    }
+}
+
+fn get_format_component_position(field: &parser::PositionField) -> TokenStream {
+    let ident = &field.ident;
+    let ty = &field.ty;
+    let format = if field.conversion.is_custom() {
+        let (kind, inner) = parser::split_type(ty);
+        let format = get_format_expression(&inner, &field.conversion);
+        match kind {
+            parser::Kind::Option => quote!{ self.#ident.as_ref().map(#format) },
+            _ => quote!{ Some((#format)(&self.#ident)) },
+        }
+    }
+    else {
+        quote!{ <#ty as jockey::FormattableWithPosition>::format_arg(&self.#ident) }
+    };
+    quote!{
+        if let Some(arg) = #format {
+            args.push(arg);
+        }
+    }
+}
+
+pub fn derive_to_args(input: &syn::DeriveInput) -> proc_macro2::TokenStream {
+    match parser::parse_data(input) {
+        parser::Data::Struct(data) => {
+            let mut positions: Vec<&parser::PositionField> = Vec::new();
+            let mut option_components = quote! {};
+            let mut unknown_args_component = quote! {};
+            let mut trailing_component = quote! {};
+            let mut os_trailing_component = None;
+            for field in data.fields.iter() { match field {
+                parser::Field::Ordinary(field) => {
+                    option_components.extend(get_format_component_option(field));
+                },
+                parser::Field::Unknown(field) => {
+                    let ident = &field.ident;
+                    unknown_args_component = quote! {
+                        args.extend(self.#ident.iter().map(|arg| arg.to_string()));
+                    };
+                },
+                parser::Field::Position(field) => {
+                    positions.push(field);
+                },
+                parser::Field::Trailing(field) => {
+                    let ident = &field.ident;
+                    let format = if field.is_os_string() {
+                        // to_os_args keeps the arguments that are not valid UTF-8.
+                        os_trailing_component = Some(quote! {
+                            if !self.#ident.is_empty() {
+                                args.push(std::ffi::OsString::from("--"));
+                                args.extend(self.#ident.iter().cloned());
+                            }
+                        });
+                        quote! { arg.to_string_lossy().into_owned() }
+                    }
+                    else {
+                        quote! { arg.clone() }
+                    };
+                    // The marker is accepted wherever the arguments start.
+                    trailing_component = quote! {
+                        if !self.#ident.is_empty() {
+                            args.push(String::from("--"));
+                            args.extend(self.#ident.iter().map(|arg| #format));
+                        }
+                    };
+                },
+            }}
+
+            // Positional arguments are matched by their index so they have to come first.
+            positions.sort_by_key(|field| field.position);
+            let mut position_components = quote! {};
+            for field in positions {
+                position_components.extend(get_format_component_position(field));
+            }
+
+            let to_os_args = match os_trailing_component {
+                Some(os_trailing_component) => quote! {
+                    fn to_os_args(&self) -> Vec<std::ffi::OsString> {
+                        let mut args: Vec<String> = Vec::new();
+                        #position_components
+                        #option_components
+                        #unknown_args_component
+                        let mut args: Vec<std::ffi::OsString> = args.into_iter().map(std::ffi::OsString::from).collect();
+                        #os_trailing_component
+                        args
+                    }
+                },
+                None => quote! {},
+            };
+
+            quote! {
+                fn to_args(&self) -> Vec<String> {
+                    let mut args: Vec<String> = Vec::new();
+                    #position_components
+                    #option_components
+                    #unknown_args_component
+                    #trailing_component
+                    args
+                }
+
+                #to_os_args
+            }
+        },
+        parser::Data::Enum(_) => panic!("Can only derive jockey::ToArgs from structs."),
+    }
+}
@@ -1,4 +1,4 @@
-//! This crate provides jockey's derive macro.
+//! This crate provides jockey's derive macros.
 //!
 //! ```rust
 //! # extern crate jockey;
@@ -8,7 +8,17 @@
 //! # #[derive(Default)]
 //! # struct Struct {
 //! #   pub field: String,
+//! #   pub level: Option<Level>,
 //! # }
+//!
+//! // Accepts "debug", "info" and "warning" or "warn" as values.
+//! #[derive(Parsable)]
+//! enum Level {
+//!     Debug,
+//!     Info,
+//!     #[jockey(name="warning", alias="warn")]
+//!     Warn,
+//! }
 //! # }
 //! ```
 
@@ -23,21 +33,75 @@ extern crate quote;
 #[macro_use]
 extern crate derive_new;
 
+mod bounds;
+mod case;
+mod derive_parsable;
 mod derive_parse_args;
+mod derive_schema;
+mod derive_to_args;
 mod parser;
 
 /// Implementation of `#[derive(Arguments)]` (don't use this directly).
+///
+/// Generic structs are supported. Fields whose types refer to type parameters get the bounds
+/// they need for parsing added to the where clause.
 #[proc_macro_derive(Arguments, attributes(jockey))]
 pub fn derive_arguments(tokens: proc_macro::TokenStream) -> proc_macro::TokenStream {
     let input = parse_macro_input!(tokens as syn::DeriveInput);
 
     let parse_args = derive_parse_args::derive_parse_args(&input);
+    let schema = derive_schema::derive_schema(&input);
+
+    let generics = match parser::parse_data(&input) {
+        parser::Data::Struct(data) => bounds::add_field_bounds(&input.generics, &data),
+        parser::Data::Enum(_) => panic!("Can only derive jockey::Arguments from structs."),
+    };
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
 
     let struct_ident: &syn::Ident = &input.ident;
     let result = quote!{
-        impl jockey::Arguments for #struct_ident {
+        impl #impl_generics jockey::Arguments for #struct_ident #ty_generics #where_clause {
             #parse_args
+
+            #schema
+        }
+    };
+    result.into()
+}
+
+/// Implementation of `#[derive(ToArgs)]` (don't use this directly).
+///
+/// Takes the same attributes as `#[derive(Arguments)]`. Fields whose types refer to type
+/// parameters get the bounds they need for formatting added to the where clause.
+#[proc_macro_derive(ToArgs, attributes(jockey))]
+pub fn derive_to_args(tokens: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let input = parse_macro_input!(tokens as syn::DeriveInput);
+
+    let to_args = derive_to_args::derive_to_args(&input);
+
+    let generics = match parser::parse_data(&input) {
+        parser::Data::Struct(data) => bounds::add_field_format_bounds(&input.generics, &data),
+        parser::Data::Enum(_) => panic!("Can only derive jockey::ToArgs from structs."),
+    };
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    let struct_ident: &syn::Ident = &input.ident;
+    let result = quote!{
+        impl #impl_generics jockey::ToArgs for #struct_ident #ty_generics #where_clause {
+            #to_args
         }
     };
     result.into()
 }
+
+/// Implementation of `#[derive(Parsable)]` (don't use this directly).
+///
+/// Implements `ParsableValue`, `ParsableWithOption` and `ParsableWithPosition` for enums without
+/// fields. Variants are matched by their kebab-case names unless renamed with
+/// `#[jockey(name="...")]`. Additional names can be given with `#[jockey(alias="...")]`.
+#[proc_macro_derive(Parsable, attributes(jockey))]
+pub fn derive_parsable(tokens: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let input = parse_macro_input!(tokens as syn::DeriveInput);
+
+    derive_parsable::derive_parsable(&input).into()
+}
@@ -1,15 +1,88 @@
-#[derive(Debug, Clone, new)]
+use case;
+use syn::parse::{Parse, ParseStream};
+use syn::punctuated::Punctuated;
+
+/// Field attributes that change how single values are converted.
+#[derive(Debug, Clone, Default)]
+pub struct Conversion {
+    pub delimiter: Option<char>,
+    pub parse_with: Option<syn::Path>,
+    pub validate_with: Option<syn::Path>,
+    pub format_with: Option<syn::Path>,
+    pub possible_values: Vec<String>,
+    pub range: Option<String>,
+    pub default_missing_value: Option<String>,
+
+    /// Match values against the possible values ignoring case.
+    pub ignore_case: bool,
+
+    /// Name the possible values of the value type following the `rename_all` rule of the struct.
+    pub rename_all: Option<case::RenameRule>,
+}
+
+impl Conversion {
+    /// Whether the field needs a custom conversion instead of its `Parsable*` implementation.
+    pub fn is_custom(&self) -> bool {
+        self.delimiter.is_some() || self.parse_with.is_some() || self.validate_with.is_some() || self.format_with.is_some() ||
+            !self.possible_values.is_empty() || self.range.is_some() || self.default_missing_value.is_some() || self.ignore_case ||
+            self.rename_all.is_some()
+    }
+}
+
+/// How a field type wraps the type of its values.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Kind {
+    Plain,
+    Option,
+    Vec,
+}
+
+/// Where the value of a field comes from if it is not given.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Fallback {
+    Default,
+    Required,
+    DefaultValue(String),
+}
+
+/// What to do when an option that may only be given once is given again.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DuplicateOptions {
+    Error,
+    FirstWins,
+    LastWins,
+}
+
+#[derive(Debug, Clone)]
 pub struct OrdinaryField {
     pub ident: syn::Ident,
     pub ty: syn::Type,
     pub long: Option<String>,
     pub short: Option<String>,
+    pub conversion: Conversion,
+    pub duplicate_keys: Option<String>,
+    pub fallback: Fallback,
+    pub help: Option<String>,
+
+    /// Checks the values of tuple, array and map fields, which are parsed by their own
+    /// `ParsableWithOption` implementation rather than a custom conversion.
+    pub validate_with: Option<syn::Path>,
+
+    /// Match the long option ignoring case.
+    pub ignore_case: bool,
+
+    /// Take values that look like options.
+    pub allow_hyphen_values: bool,
+
+    /// What to do if the option is given again under any of its names.
+    pub duplicate_options: DuplicateOptions,
 }
 
 #[derive(Debug, Clone, new)]
 pub struct UnknownField {
     pub ident: syn::Ident,
     pub ty: syn::Type,
+    pub help: Option<String>,
 }
 
 #[derive(Debug, Clone, new)]
@@ -17,6 +90,31 @@ pub struct PositionField {
     pub ident: syn::Ident,
     pub ty: syn::Type,
     pub position: u64,
+    pub conversion: Conversion,
+    pub fallback: Fallback,
+    pub help: Option<String>,
+
+    /// Take arguments that look like options.
+    pub allow_hyphen_values: bool,
+}
+
+/// Field taking all arguments after "--" or from a position on.
+#[derive(Debug, Clone, new)]
+pub struct TrailingField {
+    pub ident: syn::Ident,
+    pub ty: syn::Type,
+    pub position: Option<u64>,
+    pub help: Option<String>,
+}
+
+impl TrailingField {
+    /// Whether the values are `OsString` rather than `String`.
+    pub fn is_os_string(&self) -> bool {
+        match split_type(&self.ty) {
+            (Kind::Vec, syn::Type::Path(ref type_path)) => type_path.path.segments.iter().last().is_some_and(|segment| segment.ident == "OsString"),
+            _ => false,
+        }
+    }
 }
 
 #[derive(Debug, Clone, new)]
@@ -24,17 +122,153 @@ pub enum Field {
     Ordinary(OrdinaryField),
     Unknown(UnknownField),
     Position(PositionField),
+    Trailing(TrailingField),
+}
+
+/// Struct attributes.
+#[derive(Debug, Clone, Default)]
+pub struct StructSettings {
+    /// Take the values of missing fields from the `Default` implementation of the struct.
+    pub default: bool,
+
+    /// Function that validates the whole struct after parsing.
+    pub validate: Option<syn::Path>,
+
+    /// Program name, version and description shown in the help and for "--version".
+    pub name: Option<String>,
+    pub version: Option<String>,
+    pub about: Option<String>,
+
+    /// How long options are derived from field names.
+    pub rename_all: Option<case::RenameRule>,
+
+    /// Accept unique prefixes of long options.
+    pub allow_abbrev: bool,
+
+    /// Prefixes of long and short options, "--" and "-" unless set.
+    pub long_prefix: Option<String>,
+    pub short_prefix: Option<String>,
+
+    /// Match all long options ignoring case.
+    pub ignore_case: bool,
+
+    /// Match options only before the first positional argument.
+    pub stop_at_first_positional: bool,
+
+    /// Match options only before the first positional argument if the `POSIXLY_CORRECT`
+    /// environment variable is set.
+    pub posixly_correct: bool,
+
+    /// Let all options and positional arguments take values that look like options.
+    pub allow_hyphen_values: bool,
+
+    /// What to do if an option is given again, unless a field sets it.
+    pub duplicate_options: Option<DuplicateOptions>,
+}
+
+impl StructSettings {
+    pub fn long_prefix(&self) -> &str {
+        self.long_prefix.as_deref().unwrap_or("--")
+    }
+
+    pub fn short_prefix(&self) -> &str {
+        self.short_prefix.as_deref().unwrap_or("-")
+    }
 }
 
 #[derive(Debug, Clone, new)]
 pub struct StructData {
-    pub ident: syn::Ident,
     pub fields: Vec<Field>,
+    pub settings: StructSettings,
+}
+
+impl StructData {
+    /// Whether a field uses the option name.
+    pub fn has_option(&self, name: &str) -> bool {
+        self.fields.iter().any(|field| match *field {
+            Field::Ordinary(ref field) => field.long.as_deref() == Some(name) || field.short.as_deref() == Some(name),
+            _ => false,
+        })
+    }
+
+    /// The automatic short and long help option, each only added if no field uses it.
+    pub fn help_options(&self) -> (Option<String>, Option<String>) {
+        self.automatic_options("h", "help")
+    }
+
+    /// The automatic short and long version option, each only added if no field uses it.
+    pub fn version_options(&self) -> (Option<String>, Option<String>) {
+        self.automatic_options("V", "version")
+    }
+
+    /// None are added if an unknown_args field collects all options that match no field.
+    fn automatic_options(&self, short: &str, long: &str) -> (Option<String>, Option<String>) {
+        if self.fields.iter().any(|field| matches!(*field, Field::Unknown(_))) {
+            return (None, None);
+        }
+        let short = format!("{}{}", self.settings.short_prefix(), short);
+        let long = format!("{}{}", self.settings.long_prefix(), long);
+        (Some(short).filter(|name| !self.has_option(name)), Some(long).filter(|name| !self.has_option(name)))
+    }
+
+    /// The long options that are matched ignoring case, including the automatic ones.
+    pub fn ignore_case_options(&self) -> Vec<String> {
+        let fields = self.fields.iter().filter_map(|field| match *field {
+            Field::Ordinary(ref field) if field.ignore_case => field.long.clone(),
+            _ => None,
+        });
+        let automatic = if self.settings.ignore_case { vec![self.help_options().1, self.version_options().1] } else { vec![] };
+        fields.chain(automatic.into_iter().flatten()).collect()
+    }
+
+    /// The long options that take a value which may be glued to them like "-Xmx1g". Only long
+    /// options with a single character prefix take glued values.
+    pub fn glued_value_options(&self) -> Vec<String> {
+        if self.settings.long_prefix().chars().count() != 1 {
+            return vec![];
+        }
+        self.fields.iter().filter_map(|field| match *field {
+            Field::Ordinary(ref field) if !split_value_types(&field.ty).is_empty() => field.long.clone(),
+            _ => None,
+        }).collect()
+    }
+
+    /// All option names including the automatic ones.
+    pub fn all_options(&self) -> Vec<String> {
+        let fields = self.fields.iter().flat_map(|field| match *field {
+            Field::Ordinary(ref field) => field.long.iter().chain(field.short.iter()).cloned().collect(),
+            _ => vec![],
+        });
+        let (help_short, help_long) = self.help_options();
+        let (version_short, version_long) = self.version_options();
+        fields.chain(help_short).chain(help_long).chain(version_short).chain(version_long).collect()
+    }
+}
+
+#[derive(Debug, Clone, new)]
+pub struct VariantData {
+    pub ident: syn::Ident,
+    pub name: String,
+    pub aliases: Vec<String>,
+}
+
+#[derive(Debug, Clone, new)]
+pub struct EnumData {
+    pub ident: syn::Ident,
+    pub variants: Vec<VariantData>,
+
+    /// Match the values ignoring case.
+    pub ignore_case: bool,
+
+    /// The value names following each `rename_all` rule of a struct, in the order of the variants.
+    /// Empty if the enum sets its own rule.
+    pub renamed_values: Vec<(case::RenameRule, Vec<String>)>,
 }
 
 #[derive(Debug, Clone)]
 pub enum Data {
     Struct(StructData),
+    Enum(EnumData),
 }
 
 #[derive(Debug, Clone)]
@@ -43,78 +277,438 @@ enum Attribute {
     Short(String),
     UnknownArgs,
     Position(u64),
+    Delimiter(char),
+    DuplicateKeys(String),
+    DuplicateOptions(DuplicateOptions),
+    ParseWith(syn::Path),
+    ValidateWith(syn::Path),
+    FormatWith(syn::Path),
+    PossibleValues(Vec<String>),
+    Range(String),
+    Name(String),
+    Alias(String),
+    DefaultMissingValue(String),
+    Default,
+    Required,
+    DefaultValue(String),
+    Validate(syn::Path),
+    Version(String),
+    About(String),
+    RenameAll(case::RenameRule),
+    AllowAbbrev,
+    OptionStyle(String),
+    IgnoreCase,
+    AllowHyphenValues,
+    TrailingVarArg,
+    StopAtFirstPositional,
+    Permute,
+    PosixlyCorrect,
+    LongPrefix(String),
+    ShortPrefix(String),
+}
+
+/// Splits a field type into its kind and the type of a single value.
+pub fn split_type(ty: &syn::Type) -> (Kind, syn::Type) {
+    if let syn::Type::Path(ref type_path) = *ty {
+        if let Some(segment) = type_path.path.segments.iter().last() {
+            if let syn::PathArguments::AngleBracketed(ref arguments) = segment.arguments {
+                if let Some(syn::GenericArgument::Type(ref inner)) = arguments.args.iter().next() {
+                    if arguments.args.len() == 1 && segment.ident == "Option" {
+                        return (Kind::Option, inner.clone());
+                    }
+                    if arguments.args.len() == 1 && segment.ident == "Vec" {
+                        return (Kind::Vec, inner.clone());
+                    }
+                }
+            }
+        }
+    }
+    (Kind::Plain, ty.clone())
+}
+
+/// The types of the values that follow an option, e.g. two for a pair and none for a flag.
+pub fn split_value_types(ty: &syn::Type) -> Vec<syn::Type> {
+    let (kind, inner) = split_type(ty);
+    let ty = if kind == Kind::Plain { ty.clone() } else { inner };
+    match ty {
+        syn::Type::Tuple(ref tuple) => tuple.elems.iter().cloned().collect(),
+        syn::Type::Array(syn::TypeArray { ref elem, len: syn::Expr::Lit(syn::ExprLit { lit: syn::Lit::Int(ref len), .. }), .. }) => {
+            (0..len.value()).map(|_| (**elem).clone()).collect()
+        },
+        syn::Type::Path(ref type_path) if type_path.path.segments.iter().last().is_some_and(|segment| segment.ident == "bool") => vec![],
+        _ => vec![ty.clone()],
+    }
+}
+
+/// Whether the type is a primitive or non-zero number, which may be negative.
+pub fn is_numeric(ty: &syn::Type) -> bool {
+    match *ty {
+        syn::Type::Path(ref type_path) => match type_path.path.segments.iter().last() {
+            Some(segment) => {
+                let name = segment.ident.to_string();
+                let name = name.trim_start_matches("NonZero").to_lowercase();
+                ["i8", "i16", "i32", "i64", "i128", "isize", "u8", "u16", "u32", "u64", "u128", "usize", "f32", "f64"].contains(&name.as_str())
+            },
+            None => false,
+        },
+        _ => false,
+    }
+}
+
+/// The value type of fields of type "Option<Option<T>>", which take an optional value.
+pub fn split_optional_value_type(ty: &syn::Type) -> Option<syn::Type> {
+    match split_type(ty) {
+        (Kind::Option, inner) => match split_type(&inner) {
+            (Kind::Option, innermost) => Some(innermost),
+            _ => None,
+        },
+        _ => None,
+    }
 }
 
 pub fn parse_data(input: &syn::DeriveInput) -> Data {
     match input.data {
-        syn::Data::Struct(ref struct_data) => Data::Struct(parse_data_from_struct(&input.ident, struct_data)),
-        _ => panic!("Can only derive jockey::Arguments from struct."),
+        syn::Data::Struct(ref struct_data) => Data::Struct(parse_data_from_struct(&input.attrs, struct_data)),
+        syn::Data::Enum(ref enum_data) => Data::Enum(parse_data_from_enum(&input.ident, &input.attrs, enum_data)),
+        _ => panic!("Can only derive jockey traits from structs and enums."),
     }
 }
 
-fn parse_data_from_struct(ident: &syn::Ident, data: &syn::DataStruct) -> StructData {
+fn parse_data_from_struct(attrs: &[syn::Attribute], data: &syn::DataStruct) -> StructData {
     let fields = match data.fields {
         syn::Fields::Named(ref fields) => &fields.named,
         _ => panic!("Can only derive jockey::Arguments from struct with named fields"),
     };
 
+    let mut settings = StructSettings::default();
+    for attr in parse_attributes(attrs) {
+        match attr {
+            Attribute::Default => { settings.default = true; },
+            Attribute::Validate(val) => { settings.validate = Some(val); },
+            Attribute::Name(val) => { settings.name = Some(val); },
+            Attribute::Version(val) => { settings.version = Some(val); },
+            Attribute::About(val) => { settings.about = Some(val); },
+            Attribute::RenameAll(val) => { settings.rename_all = Some(val); },
+            Attribute::AllowAbbrev => { settings.allow_abbrev = true; },
+            Attribute::OptionStyle(val) => match val.as_ref() {
+                "gnu" => { settings.long_prefix = None; settings.short_prefix = None; },
+                "single_dash" => { settings.long_prefix = Some(String::from("-")); settings.short_prefix = None; },
+                _ => panic!("Unknown option_style: {} (expected gnu or single_dash)", val),
+            },
+            Attribute::LongPrefix(val) => { settings.long_prefix = Some(val); },
+            Attribute::ShortPrefix(val) => { settings.short_prefix = Some(val); },
+            Attribute::IgnoreCase => { settings.ignore_case = true; },
+            Attribute::StopAtFirstPositional => { settings.stop_at_first_positional = true; settings.posixly_correct = false; },
+            Attribute::Permute => { settings.stop_at_first_positional = false; settings.posixly_correct = false; },
+            Attribute::PosixlyCorrect => { settings.stop_at_first_positional = false; settings.posixly_correct = true; },
+            Attribute::AllowHyphenValues => { settings.allow_hyphen_values = true; },
+            Attribute::DuplicateOptions(val) => { settings.duplicate_options = Some(val); },
+            _ => panic!("Only default, validate, name, version, about, rename_all, allow_abbrev, option_style, long_prefix, short_prefix, \
+                ignore_case, stop_at_first_positional, permute, posixly_correct, allow_hyphen_values and duplicate_options can be used on structs"),
+        }
+    }
+    if settings.long_prefix().is_empty() || settings.short_prefix().is_empty() {
+        panic!("Option prefixes cannot be empty");
+    }
+
     let field_data = fields.iter().map(|field| {
         let ident = field.ident.clone().unwrap();
         let ty = field.ty.clone();
 
         let mut is_unknown_args = false;
+        let mut is_trailing = false;
         let mut is_positional = false;
         let mut long_option = None;
         let mut short_option = None;
         let mut position = None;
+        let mut conversion = Conversion::default();
+        let mut duplicate_keys = None;
+        let mut duplicate_options = None;
+        let mut fallback = Fallback::Default;
+        let mut ignore_case = settings.ignore_case;
+        let mut allow_hyphen_values = None;
+        let help = parse_doc_comment(&field.attrs);
 
         for attr in parse_attributes(&field.attrs) {
             match attr {
-                Attribute::Long(val) => long_option = Some(String::from("--") + &val),
-                Attribute::Short(val) => short_option = Some(String::from("-") + &val),
+                Attribute::Long(val) => long_option = Some(String::from(settings.long_prefix()) + &val),
+                Attribute::Short(val) => short_option = Some(String::from(settings.short_prefix()) + &val),
                 Attribute::UnknownArgs => { is_unknown_args = true; },
+                Attribute::TrailingVarArg => { is_trailing = true; },
+                Attribute::AllowHyphenValues => { allow_hyphen_values = Some(true); },
                 Attribute::Position(pos) => { is_positional = true; position = Some(pos); },
+                Attribute::Delimiter(val) => { conversion.delimiter = Some(val); },
+                Attribute::DuplicateKeys(val) => { duplicate_keys = Some(val); },
+                Attribute::DuplicateOptions(val) => { duplicate_options = Some(val); },
+                Attribute::ParseWith(val) => { conversion.parse_with = Some(val); },
+                Attribute::ValidateWith(val) => { conversion.validate_with = Some(val); },
+                Attribute::FormatWith(val) => { conversion.format_with = Some(val); },
+                Attribute::PossibleValues(val) => { conversion.possible_values = val; },
+                Attribute::Range(val) => { conversion.range = Some(val); },
+                Attribute::DefaultMissingValue(val) => { conversion.default_missing_value = Some(val); },
+                Attribute::Default => { fallback = Fallback::Default; },
+                Attribute::Required => { fallback = Fallback::Required; },
+                Attribute::DefaultValue(val) => { fallback = Fallback::DefaultValue(val); },
+                Attribute::IgnoreCase => {
+                    ignore_case = true;
+                    conversion.ignore_case = takes_single_value(&ty);
+                },
+                Attribute::Validate(_) => panic!("validate can only be used on structs, use validate_with on fields"),
+                Attribute::Version(_) | Attribute::About(_) | Attribute::RenameAll(_) => panic!("version, about and rename_all can only be used on structs and enums"),
+                Attribute::Name(_) | Attribute::Alias(_) => panic!("name and alias can only be used on enum variants and structs"),
+                Attribute::AllowAbbrev | Attribute::OptionStyle(_) | Attribute::LongPrefix(_) | Attribute::ShortPrefix(_) |
+                Attribute::StopAtFirstPositional | Attribute::Permute | Attribute::PosixlyCorrect => {
+                    panic!("allow_abbrev, option_style, long_prefix, short_prefix, stop_at_first_positional, permute and posixly_correct \
+                        can only be used on structs")
+                },
             }
         }
 
-        if !is_unknown_args && !is_positional {
+        // The struct attribute matches the values of all fields ignoring case, like the field one.
+        if settings.ignore_case && !is_unknown_args && !is_trailing {
+            conversion.ignore_case = takes_single_value(&ty);
+        }
+        if conversion.ignore_case {
+            check_ignore_case_collisions(&conversion.possible_values, "Possible value");
+        }
+
+        // Values of types like enums follow the rule of the struct unless the field converts or
+        // lists them itself.
+        if !is_unknown_args && !is_trailing && takes_single_value(&ty) && conversion.parse_with.is_none() &&
+            conversion.format_with.is_none() && conversion.possible_values.is_empty() {
+            conversion.rename_all = settings.rename_all;
+        }
+
+        if allow_hyphen_values.is_some() && (is_unknown_args || is_trailing) {
+            panic!("allow_hyphen_values can only be used on options and positional arguments");
+        }
+        let allow_hyphen_values = allow_hyphen_values.unwrap_or(settings.allow_hyphen_values);
+
+        if is_trailing {
+            if is_unknown_args || conversion.is_custom() || duplicate_keys.is_some() || duplicate_options.is_some() || fallback != Fallback::Default {
+                panic!("trailing_var_arg can only be combined with position");
+            }
+            let field = TrailingField::new(ident, ty, position, help);
+            let element = match split_type(&field.ty) {
+                (Kind::Vec, syn::Type::Path(ref type_path)) => type_path.path.segments.iter().last().map(|segment| segment.ident.to_string()),
+                _ => None,
+            };
+            if element.as_deref() != Some("String") && element.as_deref() != Some("OsString") {
+                panic!("trailing_var_arg can only be used on Vec<String> and Vec<OsString> fields");
+            }
+            Field::Trailing(field)
+        }
+        else if !is_unknown_args && !is_positional {
             if long_option.is_none() {
-                long_option = Some(String::from("--") + &ident.to_string().replace("_", "-"));
+                let name = match settings.rename_all {
+                    Some(rule) => rule.apply(&ident.to_string()),
+                    None => ident.to_string().replace("_", "-"),
+                };
+                long_option = Some(String::from(settings.long_prefix()) + &name);
+            }
+            if conversion.is_custom() && split_value_types(&ty).is_empty() {
+                panic!("Conversion attributes cannot be used on flags");
             }
-            Field::Ordinary(OrdinaryField::new(ident, ty, long_option, short_option))
+            let mut validate_with = None;
+            if conversion.is_custom() && !takes_single_value(&ty) {
+                validate_with = conversion.validate_with.take();
+                if conversion.is_custom() {
+                    panic!("Only validate_with can be used on tuple, array and map options");
+                }
+            }
+            Field::Ordinary(OrdinaryField {
+                ident, ty, long: long_option, short: short_option, conversion, duplicate_keys, fallback, help, validate_with, ignore_case, allow_hyphen_values,
+                duplicate_options: duplicate_options.or(settings.duplicate_options).unwrap_or(DuplicateOptions::Error),
+            })
+        }
+        else if conversion.delimiter.is_some() || conversion.default_missing_value.is_some() || duplicate_keys.is_some() || duplicate_options.is_some() {
+            panic!("value_delimiter, default_missing_value, duplicate_keys and duplicate_options can only be used on options");
         }
         else if is_unknown_args && !is_positional {
-            Field::Unknown(UnknownField::new(ident, ty))
+            if conversion.is_custom() || fallback != Fallback::Default {
+                panic!("Conversion attributes, required and default_value cannot be used on the unknown_args field");
+            }
+            Field::Unknown(UnknownField::new(ident, ty, help))
         }
         else if !is_unknown_args && is_positional {
-            Field::Position(PositionField::new(ident, ty, position.unwrap()))
+            Field::Position(PositionField::new(ident, ty, position.unwrap(), conversion, fallback, help, allow_hyphen_values))
         }
         else {
             panic!();
         }
     }).collect();
 
-    StructData::new(ident.clone(), field_data)
+    let data = StructData::new(field_data, settings);
+
+    // Names derived with rename_all may collide, e.g. fooBar and foobar with "lowercase".
+    let mut names: Vec<(&String, &syn::Ident)> = Vec::new();
+    for field in data.fields.iter() {
+        if let Field::Ordinary(ref field) = *field {
+            for name in field.long.iter().chain(field.short.iter()) {
+                if let Some(&(_, other)) = names.iter().find(|&&(other_name, _)| other_name == name) {
+                    panic!("Fields {} and {} both use the option {}", other, field.ident, name);
+                }
+                names.push((name, &field.ident));
+            }
+        }
+    }
+    let long_options: Vec<String> = data.all_options().into_iter().filter(|name| name.starts_with(data.settings.long_prefix())).collect();
+    for name in data.ignore_case_options() {
+        for other in long_options.iter() {
+            if *other != name && other.to_lowercase() == name.to_lowercase() {
+                panic!("Options {} and {} are equal when case is ignored", name, other);
+            }
+        }
+    }
+    data
 }
 
-fn parse_attributes(attrs: &Vec<syn::Attribute>) -> Vec<Attribute> {
+fn parse_data_from_enum(ident: &syn::Ident, attrs: &[syn::Attribute], data: &syn::DataEnum) -> EnumData {
+    let mut rename_all = None;
+    let mut ignore_case = false;
+    for attr in parse_attributes(attrs) {
+        match attr {
+            Attribute::RenameAll(val) => { rename_all = Some(val); },
+            Attribute::IgnoreCase => { ignore_case = true; },
+            _ => panic!("Only rename_all and ignore_case can be used on enums"),
+        }
+    }
+
+    let (variants, explicit_names): (Vec<VariantData>, Vec<Option<String>>) = data.variants.iter().map(|variant| {
+        if variant.fields != syn::Fields::Unit {
+            panic!("Can only derive jockey::Parsable for enums without fields");
+        }
+        let ident = variant.ident.clone();
+
+        let mut explicit_name = None;
+        let mut aliases = Vec::new();
+
+        for attr in parse_attributes(&variant.attrs) {
+            match attr {
+                Attribute::Name(val) => { explicit_name = Some(val); },
+                Attribute::Alias(val) => { aliases.push(val); },
+                _ => panic!("Only name and alias can be used on enum variants"),
+            }
+        }
+
+        let name = explicit_name.clone().unwrap_or_else(|| rename_all.unwrap_or(case::RenameRule::Kebab).apply(&ident.to_string()));
+        (VariantData::new(ident, name, aliases), explicit_name)
+    }).unzip();
+
+    // A rule under which two values would share a name is left out, so the values keep their own
+    // names in structs that use it.
+    let renamed_values = if rename_all.is_some() { vec![] } else {
+        case::RenameRule::ALL.iter().map(|&rule| {
+            let names: Vec<String> = variants.iter().zip(explicit_names.iter()).map(|(variant, explicit_name)| {
+                explicit_name.clone().unwrap_or_else(|| rule.apply(&variant.ident.to_string()))
+            }).collect();
+            (rule, names)
+        }).filter(|(_, names)| {
+            names.iter().enumerate().all(|(i, name)| variants.iter().zip(names.iter()).enumerate().all(|(j, (other, other_name))| {
+                i == j || (other_name != name && !other.aliases.contains(name))
+            }))
+        }).collect()
+    };
+
+    let mut names: Vec<&String> = variants.iter().flat_map(|variant| Some(&variant.name).into_iter().chain(variant.aliases.iter())).collect();
+    names.sort();
+    for pair in names.windows(2) {
+        if pair[0] == pair[1] {
+            panic!("Value name used more than once: {}", pair[0]);
+        }
+    }
+    if ignore_case {
+        let names: Vec<String> = names.into_iter().cloned().collect();
+        check_ignore_case_collisions(&names, "Value name");
+    }
+
+    EnumData::new(ident.clone(), variants, ignore_case, renamed_values)
+}
+
+/// Panics if two of the names are equal when case is ignored.
+fn check_ignore_case_collisions(names: &[String], what: &str) {
+    for (i, name) in names.iter().enumerate() {
+        for other in names[i + 1..].iter() {
+            if name.to_lowercase() == other.to_lowercase() {
+                panic!("{}s {} and {} are equal when case is ignored", what, name, other);
+            }
+        }
+    }
+}
+
+/// Whether a field type takes a single value that can be matched ignoring case, which excludes
+/// flags, tuples, arrays and maps.
+pub fn takes_single_value(ty: &syn::Type) -> bool {
+    let (_, inner) = split_type(ty);
+    match inner {
+        syn::Type::Path(ref type_path) => match type_path.path.segments.iter().last() {
+            Some(segment) => segment.ident != "bool" && segment.ident != "HashMap" && segment.ident != "BTreeMap",
+            None => false,
+        },
+        _ => false,
+    }
+}
+
+fn parse_attributes(attrs: &[syn::Attribute]) -> Vec<Attribute> {
     attrs.iter().flat_map(|attr| {
         match parse_attribute(attr) {
             Some(list) => list.iter().map(|attr| match attr {
-                (key, Some(val), None) => match key.as_ref() {
-                    "long_option" => Attribute::Long(val.to_string()),
-                    "short_option" => Attribute::Short(val.to_string()),
+                (key, Some(AttributeValue::Lit(syn::Lit::Str(val)))) => match key.as_ref() {
+                    "long_option" => Attribute::Long(val.value()),
+                    "short_option" => Attribute::Short(val.value()),
+                    "duplicate_keys" => Attribute::DuplicateKeys(val.value()),
+                    "duplicate_options" => Attribute::DuplicateOptions(match val.value().as_ref() {
+                        "error" => DuplicateOptions::Error,
+                        "first_wins" => DuplicateOptions::FirstWins,
+                        "last_wins" => DuplicateOptions::LastWins,
+                        other => panic!("Unknown duplicate_options policy: {} (expected error, first_wins or last_wins)", other),
+                    }),
+                    "parse_with" => Attribute::ParseWith(parse_path(val)),
+                    "validate_with" => Attribute::ValidateWith(parse_path(val)),
+                    "validate" => Attribute::Validate(parse_path(val)),
+                    "format_with" => Attribute::FormatWith(parse_path(val)),
+                    "range" => Attribute::Range(val.value()),
+                    "name" => Attribute::Name(val.value()),
+                    "alias" => Attribute::Alias(val.value()),
+                    "version" => Attribute::Version(val.value()),
+                    "about" => Attribute::About(val.value()),
+                    "option_style" => Attribute::OptionStyle(val.value()),
+                    "long_prefix" => Attribute::LongPrefix(val.value()),
+                    "short_prefix" => Attribute::ShortPrefix(val.value()),
+                    "rename_all" => Attribute::RenameAll(case::RenameRule::from_name(&val.value())),
+                    "default_missing_value" => Attribute::DefaultMissingValue(val.value()),
+                    "default_value" => Attribute::DefaultValue(val.value()),
                     _ => panic!("Unknown attribute: {}", key),
                 },
-                (key, None, Some(val)) => match key.as_ref() {
-                    "position" => Attribute::Position(*val),
+                (key, Some(AttributeValue::Lit(syn::Lit::Int(val)))) => match key.as_ref() {
+                    "position" => Attribute::Position(val.value()),
                     _ => panic!("Unknown attribute: {}", key),
-                }
-                (key, None, None) => match key.as_ref() {
+                },
+                (key, Some(AttributeValue::Lit(syn::Lit::Char(val)))) => match key.as_ref() {
+                    "value_delimiter" => Attribute::Delimiter(val.value()),
+                    _ => panic!("Unknown attribute: {}", key),
+                },
+                (key, Some(AttributeValue::List(list))) => match key.as_ref() {
+                    "possible_values" => Attribute::PossibleValues(list.iter().map(|lit| match lit {
+                        syn::Lit::Str(val) => val.value(),
+                        _ => panic!("Bad value for attribute: {} (expected string literals)", key),
+                    }).collect()),
+                    _ => panic!("Unknown attribute: {}", key),
+                },
+                (key, None) => match key.as_ref() {
                     "unknown_args" => Attribute::UnknownArgs,
+                    "default" => Attribute::Default,
+                    "required" => Attribute::Required,
+                    "allow_abbrev" => Attribute::AllowAbbrev,
+                    "ignore_case" => Attribute::IgnoreCase,
+                    "allow_hyphen_values" => Attribute::AllowHyphenValues,
+                    "stop_at_first_positional" => Attribute::StopAtFirstPositional,
+                    "trailing_var_arg" => Attribute::TrailingVarArg,
+                    "permute" => Attribute::Permute,
+                    "posixly_correct" => Attribute::PosixlyCorrect,
                     _ => panic!("Unknown attribute: {}", key),
                 },
-                _ => panic!(),
+                (key, _) => panic!("Bad value for attribute: {}", key),
             }).collect(),
             None => vec![],
         }
@@ -122,30 +716,83 @@ fn parse_attributes(attrs: &Vec<syn::Attribute>) -> Vec<Attribute> {
     }).collect()
 }
 
-pub fn parse_attribute(attr: &syn::Attribute) -> Option<Vec<(String, Option<String>, Option<u64>)>> {
-    match attr.parse_meta().unwrap() {
-        syn::Meta::Word(ref ident) if ident.to_string() == "jockey" => {
-            panic!("Bad use of jockey attribute (expected List)");
+/// The first paragraph of the doc comment, joined into one line.
+fn parse_doc_comment(attrs: &[syn::Attribute]) -> Option<String> {
+    let mut lines = Vec::new();
+    for attr in attrs.iter().filter(|attr| attr.path.is_ident("doc")) {
+        if let Ok(syn::Meta::NameValue(syn::MetaNameValue { lit: syn::Lit::Str(ref val), .. })) = attr.parse_meta() {
+            let line = val.value().trim().to_string();
+            if line.is_empty() && !lines.is_empty() {
+                break;
+            }
+            if !line.is_empty() {
+                lines.push(line);
+            }
         }
-        syn::Meta::NameValue(ref name_value) if name_value.ident == "jockey" => {
-            panic!("Bad use of jockey attribute (expected List)");
-        },
-        syn::Meta::List(ref list) if list.ident == "jockey" => {
-            Some(list.nested.iter().map(|nested| {
-                match nested {
-                    syn::NestedMeta::Meta(ref meta) => match meta {
-                        syn::Meta::NameValue(ref name_value) => match name_value.lit {
-                            syn::Lit::Str(ref value) => (name_value.ident.to_string(), Some(value.value()), None),
-                            syn::Lit::Int(ref value) => (name_value.ident.to_string(), None, Some(value.value())),
-                            _ => panic!("Bad use of jockey attribute (expected string literal"),
-                        },
-                        syn::Meta::Word(ref ident) => (ident.to_string(), None, None),
-                        _ => panic!("Bad use of jockey attribute (expected Word or NameValue)"),
-                    },
-                    _ => panic!("Bad use of jockey attribute (expected Meta)"),
-                }
-            }).collect())
-        },
-        _ => None,
+    }
+    if lines.is_empty() { None } else { Some(lines.join(" ")) }
+}
+
+fn parse_path(val: &syn::LitStr) -> syn::Path {
+    match val.parse() {
+        Ok(path) => path,
+        Err(_) => panic!("Expected a path to a function: {}", val.value()),
+    }
+}
+
+/// Value of an entry in a jockey attribute.
+#[derive(Debug, Clone)]
+pub enum AttributeValue {
+    Lit(syn::Lit),
+    List(Vec<syn::Lit>),
+}
+
+/// A `key`, `key = literal` or `key = [literal, ...]` entry in a jockey attribute.
+struct AttributeEntry {
+    key: syn::Ident,
+    value: Option<AttributeValue>,
+}
+
+impl Parse for AttributeEntry {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let key = input.parse()?;
+        let value = if input.peek(Token![=]) {
+            input.parse::<Token![=]>()?;
+            if input.peek(syn::token::Bracket) {
+                let content;
+                bracketed!(content in input);
+                let list = Punctuated::<syn::Lit, Token![,]>::parse_terminated(&content)?;
+                Some(AttributeValue::List(list.into_iter().collect()))
+            }
+            else {
+                Some(AttributeValue::Lit(input.parse()?))
+            }
+        }
+        else {
+            None
+        };
+        Ok(AttributeEntry { key, value })
+    }
+}
+
+/// The parenthesized entries of a jockey attribute.
+struct AttributeEntries(Vec<AttributeEntry>);
+
+impl Parse for AttributeEntries {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let content;
+        parenthesized!(content in input);
+        let entries = Punctuated::<AttributeEntry, Token![,]>::parse_terminated(&content)?;
+        Ok(AttributeEntries(entries.into_iter().collect()))
+    }
+}
+
+pub fn parse_attribute(attr: &syn::Attribute) -> Option<Vec<(String, Option<AttributeValue>)>> {
+    if !attr.path.is_ident("jockey") {
+        return None;
+    }
+    match syn::parse2::<AttributeEntries>(attr.tts.clone()) {
+        Ok(AttributeEntries(entries)) => Some(entries.into_iter().map(|entry| (entry.key.to_string(), entry.value)).collect()),
+        Err(err) => panic!("Bad use of jockey attribute: {}", err),
     }
 }
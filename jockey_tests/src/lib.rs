@@ -1,9 +1,16 @@
+#![allow(dead_code, clippy::field_reassign_with_default, clippy::into_iter_on_ref)]
+
 extern crate jockey;
 #[macro_use]
 extern crate jockey_derive;
+#[cfg(test)]
+extern crate quickcheck;
 
 #[cfg(test)]
-use jockey::{Arguments, Result};
+use jockey::{Arguments, Result, ToArgs};
+use std::collections::{BTreeMap, HashMap};
+use std::ffi::OsString;
+use std::str::FromStr;
 
 #[derive(Arguments, Default, Debug, PartialEq)]
 struct TestArguments {
@@ -171,6 +178,11 @@ pub fn parse_unknown_args() {
         expected.string = "foo".into();
         expected.argn = vec!["a1".into(), "a2".into()];
         assert_eq!(actual, expected);
+    }{
+        let actual = parse2(&vec!["dummy", "-h", "--version"]).unwrap();
+        let mut expected = TestArguments2::default();
+        expected.argn = vec!["-h".into(), "--version".into()];
+        assert_eq!(actual, expected);
     }
 }
 
@@ -182,6 +194,35 @@ pub fn parse_multi_args() {
     assert_eq!(actual, expected);
 }
 
+#[test]
+pub fn update_args() {
+    {
+        let mut actual = TestArguments2::default();
+        actual.string = "config".into();
+        actual.multi = vec!["m1".into()];
+        actual.update_from_args(vec!["dummy", "--multi", "m2", "a1"].into_iter().map(|x| x.to_string())).unwrap();
+        let mut expected = TestArguments2::default();
+        expected.string = "config".into();
+        expected.multi = vec!["m1".into(), "m2".into()];
+        expected.argn = vec!["a1".into()];
+        assert_eq!(actual, expected);
+    }{
+        let mut actual = TestArguments::default();
+        actual.option = Some("config".into());
+        actual.update_from_args(vec!["dummy", "-o", "foo", "-f"].into_iter().map(|x| x.to_string())).unwrap();
+        let mut expected = TestArguments::default();
+        expected.option = Some("foo".into());
+        expected.flag = true;
+        assert_eq!(actual, expected);
+    }{
+        let mut actual = TestArguments::default();
+        actual.option = Some("config".into());
+        let result = actual.update_from_args(vec!["dummy", "-o", "foo", "--bar"].into_iter().map(|x| x.to_string()));
+        assert_eq!(result, Err(jockey::Error::UnknownOption("--bar".into())));
+        assert_eq!(actual.option, Some("config".into()));
+    }
+}
+
 #[derive(Arguments, Default, Debug, PartialEq)]
 struct TestArguments3 {
     #[jockey(position=1)]
@@ -204,3 +245,1401 @@ pub fn parse_positional_args() {
     expected.flag = true;
     assert_eq!(actual, expected);
 }
+
+#[derive(Arguments, Default, Debug, PartialEq)]
+struct TestArguments34 {
+    /// The subcommand to run.
+    #[jockey(position=1)]
+    pub subcommand: Option<String>,
+
+    /// Enables the flag.
+    ///
+    /// Not part of the help.
+    pub flag: bool,
+}
+
+#[cfg(test)]
+fn report34(args: &[&str]) -> (std::result::Result<TestArguments34, i32>, String, String) {
+    let mut out = Vec::new();
+    let mut err = Vec::new();
+    let result = TestArguments34::parse_or_report(args.iter().map(|x| x.to_string()), &mut out, &mut err);
+    (result, String::from_utf8(out).unwrap(), String::from_utf8(err).unwrap())
+}
+
+#[test]
+pub fn report_help_and_errors() {
+    {
+        let (result, out, err) = report34(&["/bin/prog", "sub", "--help"]);
+        assert_eq!(result, Err(0));
+        assert_eq!(out, "Usage: prog [OPTIONS] [SUBCOMMAND]\n\n\
+            Arguments:\n  [SUBCOMMAND]   The subcommand to run.\n\n\
+            Options:\n      --flag     Enables the flag.\n  -h, --help     Print help\n  -V, --version  Print version\n");
+        assert_eq!(err, "");
+    }{
+        let (result, out, err) = report34(&["/bin/prog", "-V"]);
+        assert_eq!(result, Err(0));
+        assert_eq!(out, format!("prog {}\n", env!("CARGO_PKG_VERSION")));
+        assert_eq!(err, "");
+    }{
+        let (result, out, err) = report34(&["/bin/prog", "sub", "--bad"]);
+        assert_eq!(result, Err(2));
+        assert_eq!(out, "");
+        assert_eq!(err, "prog: Unknown option: --bad\nUsage: prog [OPTIONS] [SUBCOMMAND]\nTry 'prog --help' for more information.\n");
+    }{
+        let (result, out, err) = report34(&["/bin/prog", "sub", "--flag"]);
+        let mut expected = TestArguments34::default();
+        expected.subcommand = Some("sub".into());
+        expected.flag = true;
+        assert_eq!(result, Ok(expected));
+        assert_eq!((out.as_str(), err.as_str()), ("", ""));
+    }
+}
+
+#[derive(Arguments, Default, Debug, PartialEq)]
+#[jockey(name="tool", version="1.2.3", about="Does things.")]
+pub struct TestArguments16 {
+    /// Overrides the automatic short option.
+    #[jockey(short_option="V")]
+    pub verbose: bool,
+}
+
+#[test]
+pub fn parse_version_args() {
+    let iter = vec!["dummy", "--version"].into_iter().map(|x| x.to_string());
+    assert_eq!(TestArguments16::parse_args(iter), Err(jockey::Error::VersionRequested));
+
+    let iter = vec!["dummy", "-V"].into_iter().map(|x| x.to_string());
+    assert_eq!(TestArguments16::parse_args(iter), Ok(TestArguments16 { verbose: true }));
+
+    assert_eq!(TestArguments16::version("prog"), "tool 1.2.3");
+    assert_eq!(TestArguments16::help("prog"), "Does things.\n\nUsage: tool [OPTIONS]\n\nOptions:\n  \
+        -V, --verbose  Overrides the automatic short option.\n  -h, --help     Print help\n      --version  Print version\n");
+}
+
+#[test]
+pub fn export_schema() {
+    let schema = TestArguments16::schema();
+    assert_eq!(schema.name, Some("tool".into()));
+    assert_eq!(schema.options.len(), 3);
+    assert_eq!(schema.options[2].long, Some("--version".into()));
+    assert_eq!(schema.options[2].short, None);
+    assert_eq!(schema.to_json(), concat!(
+        r#"{"name":"tool","version":"1.2.3","about":"Does things.","options":["#,
+        r#"{"long":"--verbose","short":"-V","value_names":[],"optional_value":false,"type_name":"bool","multiple":false,"required":false,"default_value":null,"possible_values":[],"range":null,"help":"Overrides the automatic short option."},"#,
+        r#"{"long":"--help","short":"-h","value_names":[],"optional_value":false,"type_name":"bool","multiple":false,"required":false,"default_value":null,"possible_values":[],"range":null,"help":"Print help"},"#,
+        r#"{"long":"--version","short":null,"value_names":[],"optional_value":false,"type_name":"bool","multiple":false,"required":false,"default_value":null,"possible_values":[],"range":null,"help":"Print version"}],"#,
+        r#""arguments":[]}"#));
+
+    let schema = TestArguments12::schema();
+    let name = schema.options.iter().find(|option| option.long == Some("--name".into())).unwrap();
+    assert!(name.required);
+    assert_eq!(name.type_name, "String");
+    let argument = &schema.arguments[0];
+    assert_eq!((argument.name.as_str(), argument.position, argument.required), ("COMMAND", Some(1), true));
+    let tag = schema.options.iter().find(|option| option.long == Some("--tag".into())).unwrap();
+    assert_eq!((tag.multiple, tag.default_value.as_deref()), (true, Some("a,b")));
+}
+
+#[derive(Arguments, ToArgs, Default, Debug, PartialEq)]
+pub struct TestArguments4 {
+    #[jockey(value_delimiter=',')]
+    pub tag: Vec<String>,
+
+    #[jockey(short_option="p", value_delimiter=':')]
+    pub port: Vec<u16>,
+}
+
+#[cfg(test)]
+fn parse4(args: &Vec<&str>) -> Result<TestArguments4> {
+    let iter = args.iter().map(|x| x.to_string());
+    <TestArguments4 as Arguments>::parse_args(iter)
+}
+
+#[test]
+pub fn parse_delimited_args() {
+    {
+        let actual = parse4(&vec!["dummy", "--tag", "a,b", "--tag", "c"]).unwrap();
+        let mut expected = TestArguments4::default();
+        expected.tag = vec!["a".into(), "b".into(), "c".into()];
+        assert_eq!(actual, expected);
+    }{
+        let actual = parse4(&vec!["dummy", "--tag=a\\,b,c", "-p", "80:443", "--port", "8080"]).unwrap();
+        let mut expected = TestArguments4::default();
+        expected.tag = vec!["a,b".into(), "c".into()];
+        expected.port = vec![80, 443, 8080];
+        assert_eq!(actual, expected);
+    }{
+        let actual = parse4(&vec!["dummy", "--tag", ""]).unwrap();
+        let mut expected = TestArguments4::default();
+        expected.tag = vec!["".into()];
+        assert_eq!(actual, expected);
+    }
+
+    match parse4(&vec!["dummy", "--port", "80:http:443"]) {
+        Ok(_) => panic!(),
+        Err(error) => assert_eq!(error, jockey::Error::InvalidValue("--port".into(), "http".into(), "invalid digit found in string".into())),
+    }
+}
+
+#[derive(Arguments, ToArgs, Default, Debug, PartialEq)]
+pub struct TestArguments5 {
+    #[jockey(short_option="D")]
+    pub define: HashMap<String, String>,
+
+    #[jockey(duplicate_keys="error")]
+    pub set: BTreeMap<String, u32>,
+
+    #[jockey(duplicate_keys="collect")]
+    pub label: BTreeMap<String, Vec<String>>,
+}
+
+#[cfg(test)]
+fn parse5(args: &Vec<&str>) -> Result<TestArguments5> {
+    let iter = args.iter().map(|x| x.to_string());
+    <TestArguments5 as Arguments>::parse_args(iter)
+}
+
+#[test]
+pub fn parse_map_args() {
+    {
+        let actual = parse5(&vec!["dummy", "-D", "a=1", "--define", "b=x=y", "-D", "a=2"]).unwrap();
+        let mut expected = TestArguments5::default();
+        expected.define.insert("a".into(), "2".into());
+        expected.define.insert("b".into(), "x=y".into());
+        assert_eq!(actual, expected);
+    }{
+        let actual = parse5(&vec!["dummy", "--set", "a=1", "--set=b=2", "--label", "k=x", "--label", "k=y"]).unwrap();
+        let mut expected = TestArguments5::default();
+        expected.set.insert("a".into(), 1);
+        expected.set.insert("b".into(), 2);
+        expected.label.insert("k".into(), vec!["x".into(), "y".into()]);
+        assert_eq!(actual, expected);
+    }
+
+    match parse5(&vec!["dummy", "--set", "a=1", "--set", "a=2"]) {
+        Ok(_) => panic!(),
+        Err(error) => assert_eq!(error, jockey::Error::DuplicateKey("--set".into(), "a".into())),
+    }
+
+    match parse5(&vec!["dummy", "--set", "a=b"]) {
+        Ok(_) => panic!(),
+        Err(error) => assert_eq!(error, jockey::Error::InvalidValue("--set".into(), "b".into(), "invalid digit found in string".into())),
+    }
+}
+
+#[test]
+pub fn update_map_args() {
+    {
+        let mut actual = parse5(&vec!["dummy", "--set", "a=1", "--label", "k=x"]).unwrap();
+        actual.update_from_args(vec!["dummy", "--set", "b=2", "--label", "k=y"].into_iter().map(|x| x.to_string())).unwrap();
+        let mut expected = TestArguments5::default();
+        expected.set.insert("a".into(), 1);
+        expected.set.insert("b".into(), 2);
+        expected.label.insert("k".into(), vec!["x".into(), "y".into()]);
+        assert_eq!(actual, expected);
+    }{
+        let mut actual = parse5(&vec!["dummy", "--set", "a=1", "--label", "k=x"]).unwrap();
+        let result = actual.update_from_args(vec!["dummy", "--label", "k=y", "--set", "a=2"].into_iter().map(|x| x.to_string()));
+        assert_eq!(result, Err(jockey::Error::DuplicateKey("--set".into(), "a".into())));
+        assert_eq!(actual, parse5(&vec!["dummy", "--set", "a=1", "--label", "k=x"]).unwrap());
+    }
+}
+
+fn parse_duration(value: &str) -> std::result::Result<std::time::Duration, String> {
+    match value.trim_end_matches('s').parse() {
+        Ok(secs) if value.ends_with('s') => Ok(std::time::Duration::from_secs(secs)),
+        _ => Err("expected a number of seconds like 10s".into()),
+    }
+}
+
+fn format_duration(value: &std::time::Duration) -> String {
+    format!("{}s", value.as_secs())
+}
+
+fn validate_even(value: &u32) -> std::result::Result<(), String> {
+    match value % 2 {
+        0 => Ok(()),
+        _ => Err("must be even".into()),
+    }
+}
+
+#[derive(Arguments, ToArgs, Default, Debug, PartialEq)]
+pub struct TestArguments6 {
+    #[jockey(parse_with="parse_duration", format_with="format_duration")]
+    pub timeout: std::time::Duration,
+
+    #[jockey(validate_with="validate_even")]
+    pub even: Vec<u32>,
+
+    #[jockey(value_delimiter=',', parse_with="parse_duration", format_with="format_duration")]
+    pub intervals: Vec<std::time::Duration>,
+
+    #[jockey(position=1, parse_with="std::net::Ipv4Addr::from_str")]
+    pub host: Option<std::net::Ipv4Addr>,
+}
+
+#[cfg(test)]
+fn parse6(args: &Vec<&str>) -> Result<TestArguments6> {
+    let iter = args.iter().map(|x| x.to_string());
+    <TestArguments6 as Arguments>::parse_args(iter)
+}
+
+#[test]
+pub fn parse_custom_conversion_args() {
+    {
+        let actual = parse6(&vec!["dummy", "10.0.0.1", "--timeout", "10s", "--even", "2", "--even=4", "--intervals", "1s,2s"]).unwrap();
+        let mut expected = TestArguments6::default();
+        expected.host = Some(std::net::Ipv4Addr::new(10, 0, 0, 1));
+        expected.timeout = std::time::Duration::from_secs(10);
+        expected.even = vec![2, 4];
+        expected.intervals = vec![std::time::Duration::from_secs(1), std::time::Duration::from_secs(2)];
+        assert_eq!(actual, expected);
+    }
+
+    match parse6(&vec!["dummy", "--timeout", "10m"]) {
+        Ok(_) => panic!(),
+        Err(error) => assert_eq!(error, jockey::Error::InvalidValue("--timeout".into(), "10m".into(), "expected a number of seconds like 10s".into())),
+    }
+
+    match parse6(&vec!["dummy", "--timeout", "1s", "--timeout", "2s"]) {
+        Ok(_) => panic!(),
+        Err(error) => assert_eq!(error, jockey::Error::DuplicateOption("--timeout".into())),
+    }
+
+    match parse6(&vec!["dummy", "--even", "2", "--even", "3"]) {
+        Ok(_) => panic!(),
+        Err(error) => assert_eq!(error, jockey::Error::InvalidValue("--even".into(), "3".into(), "must be even".into())),
+    }
+
+    match parse6(&vec!["dummy", "localhost"]) {
+        Ok(_) => panic!(),
+        Err(error) => assert_eq!(error, jockey::Error::InvalidValue("argument 1".into(), "localhost".into(), "invalid IPv4 address syntax".into())),
+    }
+}
+
+#[derive(Arguments, Default, Debug, PartialEq)]
+pub struct TestArguments7 {
+    #[jockey(possible_values=["debug", "info", "warn"])]
+    pub level: Option<String>,
+
+    #[jockey(range="1..=65535")]
+    pub port: Option<u32>,
+
+    #[jockey(value_delimiter=',', range="0.0..1.0")]
+    pub weights: Vec<f64>,
+}
+
+#[cfg(test)]
+fn parse7(args: &Vec<&str>) -> Result<TestArguments7> {
+    let iter = args.iter().map(|x| x.to_string());
+    <TestArguments7 as Arguments>::parse_args(iter)
+}
+
+#[test]
+pub fn parse_restricted_args() {
+    {
+        let actual = parse7(&vec!["dummy", "--level", "info", "--port", "65535", "--weights", "0.5,0"]).unwrap();
+        let mut expected = TestArguments7::default();
+        expected.level = Some("info".into());
+        expected.port = Some(65535);
+        expected.weights = vec![0.5, 0.0];
+        assert_eq!(actual, expected);
+    }
+
+    match parse7(&vec!["dummy", "--level", "trace"]) {
+        Ok(_) => panic!(),
+        Err(error) => assert_eq!(error, jockey::Error::InvalidValue("--level".into(), "trace".into(), "possible values are debug, info, warn".into())),
+    }
+
+    match parse7(&vec!["dummy", "--port", "0"]) {
+        Ok(_) => panic!(),
+        Err(error) => assert_eq!(error, jockey::Error::InvalidValue("--port".into(), "0".into(), "must be in range 1..=65535".into())),
+    }
+
+    match parse7(&vec!["dummy", "--weights", "0.5,1.0"]) {
+        Ok(_) => panic!(),
+        Err(error) => assert_eq!(error, jockey::Error::InvalidValue("--weights".into(), "1.0".into(), "must be in range 0.0..1.0".into())),
+    }
+}
+
+#[test]
+pub fn show_restricted_values() {
+    assert_eq!(TestArguments7::help("prog"), "Usage: prog [OPTIONS]\n\nOptions:\n      \
+        --level <LEVEL>      [possible values: debug, info, warn]\n      \
+        --port <PORT>        [range: 1..=65535]\n      \
+        --weights <WEIGHTS>  [range: 0.0..1.0]\n  \
+        -h, --help               Print help\n  \
+        -V, --version            Print version\n");
+    assert!(TestArguments7::bash_completion("prog").contains("        --level)\n            COMPREPLY=($(compgen -W 'debug info warn' -- \"$cur\"))\n"));
+}
+
+#[derive(Parsable, Debug, Clone, Copy, PartialEq)]
+pub enum Level {
+    Debug,
+    Info,
+    #[jockey(name="warning", alias="warn")]
+    Warn,
+    VeryLoud,
+    HTTPTrace,
+}
+
+#[derive(Arguments, Default, Debug, PartialEq)]
+pub struct TestArguments8 {
+    pub level: Option<Level>,
+
+    #[jockey(value_delimiter=',')]
+    pub levels: Vec<Level>,
+
+    #[jockey(position=1)]
+    pub first: Option<Level>,
+}
+
+#[cfg(test)]
+fn parse8(args: &Vec<&str>) -> Result<TestArguments8> {
+    let iter = args.iter().map(|x| x.to_string());
+    <TestArguments8 as Arguments>::parse_args(iter)
+}
+
+#[test]
+pub fn parse_enum_args() {
+    {
+        let actual = parse8(&vec!["dummy", "info", "--level", "warn", "--levels", "warning,very-loud,http-trace"]).unwrap();
+        let mut expected = TestArguments8::default();
+        expected.first = Some(Level::Info);
+        expected.level = Some(Level::Warn);
+        expected.levels = vec![Level::Warn, Level::VeryLoud, Level::HTTPTrace];
+        assert_eq!(actual, expected);
+    }
+
+    assert_eq!(<Level as jockey::ParsableValue>::possible_values(), &["debug", "info", "warning", "very-loud", "http-trace"]);
+    assert_eq!(TestArguments8::schema().options[0].possible_values, vec!["debug", "info", "warning", "very-loud", "http-trace", "warn"]);
+    assert!(TestArguments8::help("prog").contains("  [FIRST]                [possible values: debug, info, warning, very-loud, http-trace, warn]\n"));
+    assert!(TestArguments8::bash_completion("prog").contains("compgen -W 'debug info warning very-loud http-trace warn'"));
+
+    match parse8(&vec!["dummy", "--level", "loud"]) {
+        Ok(_) => panic!(),
+        Err(error) => assert_eq!(error, jockey::Error::InvalidValue("--level".into(), "loud".into(), "possible values are debug, info, warning, very-loud, http-trace".into())),
+    }
+}
+
+#[derive(Arguments, ToArgs, Default, Debug, PartialEq)]
+pub struct TestArguments9 {
+    #[jockey(default_missing_value="always", possible_values=["always", "never", "auto"])]
+    pub color: Option<String>,
+
+    pub jobs: Option<Option<u32>>,
+
+    #[jockey(unknown_args)]
+    pub files: Vec<String>,
+}
+
+#[cfg(test)]
+fn parse9(args: &Vec<&str>) -> Result<TestArguments9> {
+    let iter = args.iter().map(|x| x.to_string());
+    <TestArguments9 as Arguments>::parse_args(iter)
+}
+
+#[test]
+pub fn parse_optional_value_args() {
+    {
+        let actual = parse9(&vec!["dummy", "--color", "file.txt", "--jobs", "4"]).unwrap();
+        let mut expected = TestArguments9::default();
+        expected.color = Some("always".into());
+        expected.jobs = Some(None);
+        expected.files = vec!["file.txt".into(), "4".into()];
+        assert_eq!(actual, expected);
+    }{
+        let actual = parse9(&vec!["dummy", "--color=never", "--jobs=4"]).unwrap();
+        let mut expected = TestArguments9::default();
+        expected.color = Some("never".into());
+        expected.jobs = Some(Some(4));
+        assert_eq!(actual, expected);
+    }
+
+    match parse9(&vec!["dummy", "--color=sometimes"]) {
+        Ok(_) => panic!(),
+        Err(error) => assert_eq!(error, jockey::Error::InvalidValue("--color".into(), "sometimes".into(), "possible values are always, never, auto".into())),
+    }
+}
+
+#[derive(Arguments, ToArgs, Default, Debug, PartialEq)]
+pub struct TestArguments10 {
+    pub resize: Option<(u32, u32)>,
+
+    pub range: Option<[String; 2]>,
+
+    pub point: Vec<(String, f64, f64)>,
+}
+
+#[cfg(test)]
+fn parse10(args: &Vec<&str>) -> Result<TestArguments10> {
+    let iter = args.iter().map(|x| x.to_string());
+    <TestArguments10 as Arguments>::parse_args(iter)
+}
+
+#[test]
+pub fn parse_fixed_arity_args() {
+    {
+        let actual = parse10(&vec!["dummy", "--resize", "800", "600", "--range", "START", "END", "--point", "a", "1", "2", "--point", "b", "3", "4"]).unwrap();
+        let mut expected = TestArguments10::default();
+        expected.resize = Some((800, 600));
+        expected.range = Some(["START".into(), "END".into()]);
+        expected.point = vec![("a".into(), 1.0, 2.0), ("b".into(), 3.0, 4.0)];
+        assert_eq!(actual, expected);
+    }
+
+    match parse10(&vec!["dummy", "--resize", "800"]) {
+        Ok(_) => panic!(),
+        Err(error) => assert_eq!(error, jockey::Error::MissingValues("--resize".into(), 2, 1)),
+    }
+
+    match parse10(&vec!["dummy", "--range"]) {
+        Ok(_) => panic!(),
+        Err(error) => assert_eq!(error, jockey::Error::MissingValues("--range".into(), 2, 0)),
+    }
+}
+
+pub enum Backend {
+    Local,
+    Remote(String),
+}
+
+fn parse_backend(value: &str) -> std::result::Result<Backend, String> {
+    match value {
+        "local" => Ok(Backend::Local),
+        _ => Ok(Backend::Remote(value.to_string())),
+    }
+}
+
+fn format_backend(value: &Backend) -> String {
+    match value {
+        Backend::Local => "local".into(),
+        Backend::Remote(host) => host.clone(),
+    }
+}
+
+/// Without `Default`, missing fields are set as declared.
+#[derive(Arguments)]
+pub struct TestArguments12 {
+    #[jockey(default_value="4")]
+    pub jobs: std::num::NonZeroU32,
+
+    #[jockey(required)]
+    pub name: String,
+
+    #[jockey(parse_with="parse_backend", format_with="format_backend", default_value="local")]
+    pub backend: Backend,
+
+    #[jockey(value_delimiter=',', default_value="a,b")]
+    pub tag: Vec<String>,
+
+    pub level: Option<Level>,
+
+    #[jockey(position=1, required)]
+    pub command: String,
+}
+
+#[cfg(test)]
+fn parse12(args: &Vec<&str>) -> Result<TestArguments12> {
+    let iter = args.iter().map(|x| x.to_string());
+    <TestArguments12 as Arguments>::parse_args(iter)
+}
+
+#[derive(Arguments, Debug, PartialEq)]
+#[jockey(default)]
+pub struct TestArguments13 {
+    pub port: u16,
+
+    pub tag: Vec<String>,
+
+    #[jockey(required)]
+    pub name: String,
+}
+
+impl Default for TestArguments13 {
+    fn default() -> Self {
+        TestArguments13 { port: 8080, tag: vec!["default".into()], name: String::new() }
+    }
+}
+
+#[cfg(test)]
+fn parse13(args: &Vec<&str>) -> Result<TestArguments13> {
+    let iter = args.iter().map(|x| x.to_string());
+    <TestArguments13 as Arguments>::parse_args(iter)
+}
+
+#[test]
+pub fn parse_declared_defaults() {
+    {
+        let actual = parse12(&vec!["dummy", "run", "--name", "foo"]).unwrap();
+        assert_eq!(actual.jobs.get(), 4);
+        assert_eq!(actual.name, "foo");
+        assert!(matches!(actual.backend, Backend::Local));
+        assert_eq!(actual.tag, vec!["a".to_string(), "b".to_string()]);
+        assert_eq!(actual.level, None);
+        assert_eq!(actual.command, "run");
+    }{
+        let actual = parse12(&vec!["dummy", "run", "--name", "foo", "--jobs", "2", "--backend", "example.com", "--tag", "c"]).unwrap();
+        assert_eq!(actual.jobs.get(), 2);
+        assert!(matches!(actual.backend, Backend::Remote(ref host) if host == "example.com"));
+        assert_eq!(actual.tag, vec!["c".to_string()]);
+    }{
+        let actual = parse13(&vec!["dummy", "--name", "foo", "--tag", "given"]).unwrap();
+        let expected = TestArguments13 { port: 8080, tag: vec!["given".into()], name: "foo".into() };
+        assert_eq!(actual, expected);
+    }
+
+    match parse12(&vec!["dummy", "run"]) {
+        Ok(_) => panic!(),
+        Err(error) => assert_eq!(error, jockey::Error::MissingArgument("--name".into())),
+    }
+
+    match parse12(&vec!["dummy", "--name", "foo"]) {
+        Ok(_) => panic!(),
+        Err(error) => assert_eq!(error, jockey::Error::MissingArgument("argument 1".into())),
+    }
+
+    match parse12(&vec!["dummy", "run", "--name", "foo", "--jobs", "0"]) {
+        Ok(_) => panic!(),
+        Err(error) => assert_eq!(error, jockey::Error::InvalidValue("--jobs".into(), "0".into(), "number would be zero for non-zero type".into())),
+    }
+
+    match parse13(&vec!["dummy"]) {
+        Ok(_) => panic!(),
+        Err(error) => assert_eq!(error, jockey::Error::MissingArgument("--name".into())),
+    }
+}
+
+/// One struct shared by several value types.
+#[derive(Arguments, ToArgs, Debug, PartialEq)]
+pub struct TestArguments14<T, U: Clone> where T: std::fmt::Debug {
+    #[jockey(position=1, required)]
+    pub first: T,
+
+    pub value: Option<T>,
+
+    #[jockey(value_delimiter=',')]
+    pub values: Vec<U>,
+}
+
+#[cfg(test)]
+fn parse14<T: std::fmt::Debug, U: Clone>(args: &Vec<&str>) -> Result<TestArguments14<T, U>>
+    where TestArguments14<T, U>: Arguments
+{
+    let iter = args.iter().map(|x| x.to_string());
+    <TestArguments14<T, U> as Arguments>::parse_args(iter)
+}
+
+#[test]
+pub fn parse_generic_args() {
+    {
+        let actual = parse14::<u32, String>(&vec!["dummy", "1", "--value", "2", "--values", "a,b"]).unwrap();
+        let expected = TestArguments14 { first: 1, value: Some(2), values: vec!["a".to_string(), "b".to_string()] };
+        assert_eq!(actual, expected);
+    }{
+        let actual = parse14::<Level, f64>(&vec!["dummy", "info", "--values", "0.5"]).unwrap();
+        let expected = TestArguments14 { first: Level::Info, value: None, values: vec![0.5] };
+        assert_eq!(actual, expected);
+        assert_eq!(actual.to_args(), vec!["info", "--values=0.5"]);
+    }
+
+    match parse14::<Level, f64>(&vec!["dummy", "loud"]) {
+        Ok(_) => panic!(),
+        Err(error) => assert_eq!(error, jockey::Error::InvalidValue("argument 1".into(), "loud".into(), "possible values are debug, info, warning, very-loud, http-trace".into())),
+    }
+}
+
+#[derive(Arguments, Default, Debug, PartialEq)]
+#[jockey(validate="validate_range")]
+pub struct TestArguments15 {
+    pub start: u32,
+
+    pub end: Option<u32>,
+}
+
+fn validate_range(args: &TestArguments15) -> std::result::Result<(), String> {
+    match args.end {
+        Some(end) if end <= args.start => Err(format!("--end must be after --start ({})", args.start)),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+fn parse15(args: &Vec<&str>) -> Result<TestArguments15> {
+    let iter = args.iter().map(|x| x.to_string());
+    <TestArguments15 as Arguments>::parse_args(iter)
+}
+
+#[test]
+pub fn parse_validated_args() {
+    {
+        let actual = parse15(&vec!["dummy", "--start", "1", "--end", "2"]).unwrap();
+        let mut expected = TestArguments15::default();
+        expected.start = 1;
+        expected.end = Some(2);
+        assert_eq!(actual, expected);
+    }
+
+    match parse15(&vec!["dummy", "--start", "2", "--end", "1"]) {
+        Ok(_) => panic!(),
+        Err(error) => {
+            assert_eq!(error, jockey::Error::Custom("--end must be after --start (2)".into()));
+            assert_eq!(error.to_string(), "--end must be after --start (2)");
+        },
+    }
+
+    let mut args = TestArguments15::default();
+    args.update_from_args(vec!["dummy", "--end", "0"].into_iter().map(|x| x.to_string())).unwrap();
+    assert_eq!(args.validate(), Err(jockey::Error::Custom("--end must be after --start (0)".into())));
+}
+
+#[derive(Parsable, Debug, Clone, Copy, PartialEq)]
+#[jockey(rename_all="snake_case")]
+pub enum Mode {
+    FastForward,
+    #[jockey(name="slow")]
+    SlowMotion,
+}
+
+#[derive(Arguments, Default, Debug, PartialEq)]
+#[jockey(rename_all="camelCase")]
+pub struct TestArguments17 {
+    pub output_dir: Option<String>,
+
+    pub mode: Option<Mode>,
+
+    #[jockey(long_option="legacy_name")]
+    pub legacy_value: bool,
+}
+
+#[derive(Arguments, Default, Debug, PartialEq)]
+#[jockey(rename_all="lowercase")]
+pub struct TestArguments18 {
+    pub output_dir: Option<String>,
+}
+
+#[test]
+pub fn parse_renamed_args() {
+    {
+        let iter = vec!["dummy", "--outputDir", "out", "--mode", "fast_forward", "--legacy_name"].into_iter().map(|x| x.to_string());
+        let actual = TestArguments17::parse_args(iter).unwrap();
+        let expected = TestArguments17 { output_dir: Some("out".into()), mode: Some(Mode::FastForward), legacy_value: true };
+        assert_eq!(actual, expected);
+    }{
+        let iter = vec!["dummy", "--outputdir", "out"].into_iter().map(|x| x.to_string());
+        let actual = TestArguments18::parse_args(iter).unwrap();
+        assert_eq!(actual.output_dir, Some("out".into()));
+    }
+
+    let iter = vec!["dummy", "--output-dir", "out"].into_iter().map(|x| x.to_string());
+    assert_eq!(TestArguments17::parse_args(iter), Err(jockey::Error::UnknownOption("--output-dir".into())));
+
+    assert_eq!(<Mode as jockey::ParsableValue>::possible_values(), &["fast_forward", "slow"]);
+}
+
+#[derive(Parsable, Debug, Clone, Copy, PartialEq)]
+pub enum Speed {
+    VeryFast,
+    SlowMotion,
+}
+
+#[derive(Arguments, ToArgs, Debug, PartialEq)]
+#[jockey(rename_all="camelCase")]
+struct TestArguments35 {
+    pub speed: Option<Speed>,
+
+    #[jockey(position=1)]
+    pub start_speed: Option<Speed>,
+}
+
+#[test]
+pub fn parse_renamed_values() {
+    {
+        let iter = vec!["dummy", "slowMotion", "--speed", "veryFast"].into_iter().map(|x| x.to_string());
+        let actual = TestArguments35::parse_args(iter).unwrap();
+        assert_eq!(actual, TestArguments35 { speed: Some(Speed::VeryFast), start_speed: Some(Speed::SlowMotion) });
+        assert_eq!(actual.to_args(), vec!["slowMotion".to_string(), "--speed=veryFast".to_string()]);
+    }{
+        let iter = vec!["dummy", "--speed", "very-fast"].into_iter().map(|x| x.to_string());
+        assert_eq!(TestArguments35::parse_args(iter).unwrap().speed, Some(Speed::VeryFast));
+    }
+
+    assert_eq!(<Speed as jockey::ParsableValue>::renamed_values("camelCase"), &["veryFast", "slowMotion"]);
+    assert_eq!(<Mode as jockey::ParsableValue>::renamed_values("camelCase"), &["fast_forward", "slow"]);
+}
+
+#[derive(Arguments, Default, Debug, PartialEq)]
+#[jockey(allow_abbrev)]
+pub struct TestArguments19 {
+    pub verbose: bool,
+
+    pub verify: bool,
+
+    pub output: Option<String>,
+}
+
+#[cfg(test)]
+fn parse19(args: &Vec<&str>) -> Result<TestArguments19> {
+    let iter = args.iter().map(|x| x.to_string());
+    <TestArguments19 as Arguments>::parse_args(iter)
+}
+
+#[test]
+pub fn parse_abbreviated_args() {
+    {
+        let args = vec!["dummy", "--verb", "--out=x"];
+        let mut expected = TestArguments19::default();
+        expected.verbose = true;
+        expected.output = Some("x".into());
+        assert_eq!(parse19(&args), Ok(expected));
+    }{
+        let args = vec!["dummy", "--o", "x", "--output", "y"];
+        match parse19(&args) {
+            Ok(_) => panic!(),
+            Err(error) => assert_eq!(error, jockey::Error::DuplicateOption("--output".into())),
+        }
+    }{
+        let args = vec!["dummy", "--ver"];
+        match parse19(&args) {
+            Ok(_) => panic!(),
+            Err(error) => assert_eq!(error, jockey::Error::AmbiguousOption("--ver".into(), vec!["--verbose".into(), "--verify".into(), "--version".into()])),
+        }
+    }{
+        let args = vec!["dummy", "--he"];
+        assert_eq!(parse19(&args), Err(jockey::Error::HelpRequested));
+    }
+}
+
+#[derive(Arguments, ToArgs, Default, Debug, PartialEq)]
+#[jockey(option_style="single_dash")]
+pub struct TestArguments20 {
+    /// Name to look for.
+    pub name: Option<String>,
+
+    #[jockey(long_option="Xmx", short_option="m")]
+    pub max_memory: Option<String>,
+}
+
+#[derive(Arguments, Default, Debug, PartialEq)]
+#[jockey(long_prefix="+", short_prefix="/")]
+pub struct TestArguments21 {
+    #[jockey(short_option="v")]
+    pub verbose: bool,
+
+    pub level: Option<u32>,
+}
+
+#[test]
+pub fn parse_option_styles() {
+    {
+        let iter = vec!["dummy", "-name", "x", "-Xmx=1g"].into_iter().map(|x| x.to_string());
+        let actual = TestArguments20::parse_args(iter).unwrap();
+        assert_eq!(actual, TestArguments20 { name: Some("x".into()), max_memory: Some("1g".into()) });
+        assert_eq!(actual.to_args(), vec!["-name=x", "-Xmx=1g"]);
+    }{
+        let iter = vec!["dummy", "-Xmx1g", "-namex"].into_iter().map(|x| x.to_string());
+        assert_eq!(TestArguments20::parse_args(iter), Ok(TestArguments20 { name: Some("x".into()), max_memory: Some("1g".into()) }));
+
+        let iter = vec!["dummy", "+level3"].into_iter().map(|x| x.to_string());
+        assert_eq!(TestArguments21::parse_args(iter), Ok(TestArguments21 { verbose: false, level: Some(3) }));
+
+        // Short options and options with a two character prefix take no glued values.
+        let iter = vec!["dummy", "-m1g"].into_iter().map(|x| x.to_string());
+        assert_eq!(TestArguments20::parse_args(iter), Err(jockey::Error::UnknownOption("-m1g".into())));
+
+        let iter = vec!["dummy", "--levelinfo"].into_iter().map(|x| x.to_string());
+        assert_eq!(TestArguments8::parse_args(iter), Err(jockey::Error::UnknownOption("--levelinfo".into())));
+    }{
+        let iter = vec!["dummy", "--name", "x"].into_iter().map(|x| x.to_string());
+        assert_eq!(TestArguments20::parse_args(iter), Err(jockey::Error::UnknownOption("--name".into())));
+    }{
+        let iter = vec!["dummy", "/v", "+level", "3"].into_iter().map(|x| x.to_string());
+        assert_eq!(TestArguments21::parse_args(iter), Ok(TestArguments21 { verbose: true, level: Some(3) }));
+
+        let iter = vec!["dummy", "/h"].into_iter().map(|x| x.to_string());
+        assert_eq!(TestArguments21::parse_args(iter), Err(jockey::Error::HelpRequested));
+    }
+
+    assert_eq!(TestArguments20::help("prog"), "Usage: prog [OPTIONS]\n\nOptions:\n      -name <NAME>       Name to look for.\n  -m, -Xmx <MAX_MEMORY>\n  \
+        -h, -help              Print help\n  -V, -version           Print version\n");
+    assert_eq!(TestArguments21::usage("prog"), "Usage: prog [OPTIONS]");
+    assert_eq!(TestArguments21::schema().options[0].display_name(), "/v, +verbose");
+}
+
+#[derive(Parsable, Debug, Clone, Copy, PartialEq)]
+#[jockey(ignore_case)]
+pub enum Color {
+    Red,
+    #[jockey(alias="grey")]
+    Gray,
+}
+
+#[derive(Arguments, ToArgs, Default, Debug, PartialEq)]
+#[jockey(ignore_case)]
+pub struct TestArguments22 {
+    pub verbose: bool,
+
+    pub color: Option<Color>,
+
+    #[jockey(possible_values=["fast", "slow"])]
+    pub speed: Option<String>,
+
+    #[jockey(short_option="l")]
+    pub level: Option<Level>,
+}
+
+#[derive(Arguments, Default, Debug, PartialEq)]
+pub struct TestArguments23 {
+    #[jockey(ignore_case)]
+    pub level: Option<Level>,
+
+    pub name: Option<String>,
+}
+
+#[test]
+pub fn parse_ignore_case_args() {
+    {
+        let iter = vec!["dummy", "--Verbose", "--COLOR=Grey", "--Speed", "FAST", "-l", "info"].into_iter().map(|x| x.to_string());
+        let actual = TestArguments22::parse_args(iter).unwrap();
+        let expected = TestArguments22 { verbose: true, color: Some(Color::Gray), speed: Some("fast".into()), level: Some(Level::Info) };
+        assert_eq!(actual, expected);
+        assert_eq!(actual.to_args(), vec!["--verbose", "--color=gray", "--speed=fast", "--level=info"]);
+    }{
+        // The struct attribute also matches the values of enums without ignore_case.
+        let iter = vec!["dummy", "--level", "Info"].into_iter().map(|x| x.to_string());
+        assert_eq!(TestArguments22::parse_args(iter).map(|args| args.level), Ok(Some(Level::Info)));
+
+        let iter = vec!["dummy", "--level=WARN"].into_iter().map(|x| x.to_string());
+        assert_eq!(TestArguments22::parse_args(iter).map(|args| args.level), Ok(Some(Level::Warn)));
+
+        let iter = vec!["dummy", "--level", "Loud"].into_iter().map(|x| x.to_string());
+        assert_eq!(TestArguments22::parse_args(iter), Err(jockey::Error::InvalidValue("--level".into(), "Loud".into(),
+            "possible values are debug, info, warning, very-loud, http-trace".into())));
+
+        let iter = vec!["dummy", "-L", "info"].into_iter().map(|x| x.to_string());
+        assert_eq!(TestArguments22::parse_args(iter), Err(jockey::Error::UnknownOption("-L".into())));
+
+        let iter = vec!["dummy", "--HELP"].into_iter().map(|x| x.to_string());
+        assert_eq!(TestArguments22::parse_args(iter), Err(jockey::Error::HelpRequested));
+    }{
+        let iter = vec!["dummy", "--LEVEL=Very-Loud"].into_iter().map(|x| x.to_string());
+        assert_eq!(TestArguments23::parse_args(iter), Ok(TestArguments23 { level: Some(Level::VeryLoud), name: None }));
+
+        let iter = vec!["dummy", "--Name", "x"].into_iter().map(|x| x.to_string());
+        assert_eq!(TestArguments23::parse_args(iter), Err(jockey::Error::UnknownOption("--Name".into())));
+    }
+}
+
+#[derive(Arguments, Default, Debug, PartialEq)]
+#[jockey(stop_at_first_positional)]
+pub struct TestArguments24 {
+    pub verbose: bool,
+
+    #[jockey(position=1)]
+    pub command: Option<String>,
+
+    #[jockey(unknown_args)]
+    pub command_args: Vec<String>,
+}
+
+#[derive(Arguments, Default, Debug, PartialEq)]
+#[jockey(permute)]
+pub struct TestArguments25 {
+    pub verbose: bool,
+
+    #[jockey(unknown_args)]
+    pub rest: Vec<String>,
+}
+
+#[derive(Arguments, Default, Debug, PartialEq)]
+#[jockey(stop_at_first_positional)]
+pub struct TestArguments36 {
+    pub verbose: bool,
+
+    #[jockey(position=1)]
+    pub command: Option<String>,
+
+    #[jockey(position=2)]
+    pub arg: Option<String>,
+}
+
+#[test]
+pub fn parse_stop_at_first_positional() {
+    {
+        let iter = vec!["dummy", "run", "--verbose", "--help", "-"].into_iter().map(|x| x.to_string());
+        let actual = TestArguments24::parse_args(iter).unwrap();
+        let expected = TestArguments24 { verbose: false, command: Some("run".into()), command_args: vec!["--verbose".into(), "--help".into(), "-".into()] };
+        assert_eq!(actual, expected);
+
+        let iter = vec!["dummy", "--verbose", "x", "--verbose"].into_iter().map(|x| x.to_string());
+        let actual = TestArguments24::parse_args(iter).unwrap();
+        assert_eq!(actual, TestArguments24 { verbose: true, command: None, command_args: vec!["x".into(), "--verbose".into()] });
+    }{
+        let iter = vec!["dummy", "run", "--verbose"].into_iter().map(|x| x.to_string());
+        let actual = TestArguments25::parse_args(iter).unwrap();
+        assert_eq!(actual, TestArguments25 { verbose: true, rest: vec!["run".into()] });
+    }{
+        // Positional arguments take anything once options are no longer matched.
+        let iter = vec!["dummy", "run", "--x"].into_iter().map(|x| x.to_string());
+        let actual = TestArguments36::parse_args(iter).unwrap();
+        assert_eq!(actual, TestArguments36 { verbose: false, command: Some("run".into()), arg: Some("--x".into()) });
+
+        let iter = vec!["dummy", "--x", "run"].into_iter().map(|x| x.to_string());
+        assert_eq!(TestArguments36::parse_args(iter), Err(jockey::Error::UnknownOption("--x".into())));
+    }
+}
+
+#[derive(Arguments, Default, Debug, PartialEq)]
+#[jockey(posixly_correct)]
+pub struct TestArguments38 {
+    pub verbose: bool,
+
+    #[jockey(position=1)]
+    pub command: Option<String>,
+
+    #[jockey(unknown_args)]
+    pub rest: Vec<String>,
+}
+
+#[test]
+pub fn parse_posixly_correct() {
+    // This is the only test that reads the environment variable.
+    {
+        std::env::remove_var("POSIXLY_CORRECT");
+        let iter = vec!["dummy", "run", "--verbose"].into_iter().map(|x| x.to_string());
+        let actual = TestArguments38::parse_args(iter).unwrap();
+        assert_eq!(actual, TestArguments38 { verbose: true, command: Some("run".into()), rest: vec![] });
+    }{
+        std::env::set_var("POSIXLY_CORRECT", "1");
+        let iter = vec!["dummy", "run", "--verbose"].into_iter().map(|x| x.to_string());
+        let actual = TestArguments38::parse_args(iter);
+        std::env::remove_var("POSIXLY_CORRECT");
+        assert_eq!(actual.unwrap(), TestArguments38 { verbose: false, command: Some("run".into()), rest: vec!["--verbose".into()] });
+    }
+}
+
+#[derive(Arguments, ToArgs, Default, Debug, PartialEq)]
+pub struct TestArguments26 {
+    pub verbose: bool,
+
+    #[jockey(unknown_args)]
+    pub rest: Vec<String>,
+
+    #[jockey(trailing_var_arg)]
+    pub command: Vec<String>,
+}
+
+#[derive(Arguments, ToArgs, Default, Debug, PartialEq)]
+pub struct TestArguments27 {
+    pub verbose: bool,
+
+    #[jockey(position=1)]
+    pub program: String,
+
+    #[jockey(trailing_var_arg, position=2)]
+    pub program_args: Vec<OsString>,
+}
+
+#[test]
+pub fn parse_trailing_var_arg() {
+    {
+        let iter = vec!["dummy", "x", "--verbose", "--", "cmd", "--verbose", "--", "--help"].into_iter().map(|x| x.to_string());
+        let actual = TestArguments26::parse_args(iter).unwrap();
+        let expected = TestArguments26 {
+            verbose: true,
+            rest: vec!["x".into()],
+            command: vec!["cmd".into(), "--verbose".into(), "--".into(), "--help".into()],
+        };
+        assert_eq!(actual, expected);
+        assert_eq!(actual.to_args(), vec!["--verbose", "x", "--", "cmd", "--verbose", "--", "--help"]);
+        assert_eq!(round_trip(&actual), Ok(actual));
+
+        let iter = vec!["dummy", "--verbose", "--"].into_iter().map(|x| x.to_string());
+        let actual = TestArguments26::parse_args(iter).unwrap();
+        assert_eq!(actual, TestArguments26 { verbose: true, rest: vec![], command: vec![] });
+
+        assert!(TestArguments26::usage("prog").ends_with("[OPTIONS] [REST]... [-- COMMAND...]"));
+    }{
+        let iter = vec!["dummy", "run", "--verbose", "-x"].into_iter().map(|x| x.to_string());
+        let actual = TestArguments27::parse_args(iter).unwrap();
+        let expected = TestArguments27 { verbose: false, program: "run".into(), program_args: vec!["--verbose".into(), "-x".into()] };
+        assert_eq!(actual, expected);
+
+        let iter = vec!["dummy", "run", "--", "-x"].into_iter().map(|x| x.to_string());
+        let actual = TestArguments27::parse_args(iter).unwrap();
+        assert_eq!(actual.program_args, vec![OsString::from("--"), OsString::from("-x")]);
+
+        let iter = vec!["dummy", "--", "run", "-x"].into_iter().map(|x| x.to_string());
+        let actual = TestArguments27::parse_args(iter).unwrap();
+        assert_eq!(actual, TestArguments27 { verbose: false, program: String::new(), program_args: vec!["run".into(), "-x".into()] });
+    }
+}
+
+#[cfg(unix)]
+#[test]
+pub fn format_os_string_trailing_var_arg() {
+    use std::ffi::OsStr;
+    use std::os::unix::ffi::OsStrExt;
+
+    let raw = OsStr::from_bytes(b"caf\xe9").to_os_string();
+    let args = TestArguments27 { verbose: true, program: "run".into(), program_args: vec![raw.clone(), "-x".into()] };
+    let expected = vec![OsString::from("run"), "--verbose".into(), "--".into(), raw.clone(), "-x".into()];
+    assert_eq!(args.to_os_args(), expected);
+    assert_eq!(args.to_command("prog").get_args().collect::<Vec<&OsStr>>(), expected.iter().map(|arg| arg.as_os_str()).collect::<Vec<&OsStr>>());
+
+    // Only to_args has to replace the bytes that are not valid UTF-8.
+    assert_eq!(args.to_args(), vec!["run", "--verbose", "--", "caf\u{fffd}", "-x"]);
+}
+
+#[derive(Arguments, ToArgs, Default, Debug, PartialEq)]
+pub struct TestArguments28 {
+    pub offset: Option<i32>,
+
+    pub name: Option<String>,
+
+    #[jockey(allow_hyphen_values)]
+    pub pattern: Option<String>,
+
+    pub scale: Option<(f64, f64)>,
+
+    #[jockey(position=1)]
+    pub delta: Option<f64>,
+
+    #[jockey(unknown_args)]
+    pub rest: Vec<String>,
+}
+
+#[derive(Arguments, Default, Debug, PartialEq)]
+pub struct TestArguments29 {
+    #[jockey(short_option="1")]
+    pub one: bool,
+
+    pub offset: Option<i32>,
+
+    #[jockey(position=1, allow_hyphen_values)]
+    pub first: Option<String>,
+}
+
+#[test]
+pub fn parse_hyphen_values() {
+    {
+        let iter = vec!["dummy", "-1.5", "--offset", "-5", "--pattern", "-x", "--name=-y", "--scale", "-1", "-2e3"].into_iter().map(|x| x.to_string());
+        let actual = TestArguments28::parse_args(iter).unwrap();
+        let expected = TestArguments28 {
+            offset: Some(-5),
+            name: Some("-y".into()),
+            pattern: Some("-x".into()),
+            scale: Some((-1.0, -2000.0)),
+            delta: Some(-1.5),
+            rest: vec![],
+        };
+        assert_eq!(actual, expected);
+        assert_eq!(round_trip(&actual), Ok(actual));
+
+        let iter = vec!["dummy", "-x"].into_iter().map(|x| x.to_string());
+        let actual = TestArguments28::parse_args(iter).unwrap();
+        assert_eq!(actual, TestArguments28 { rest: vec!["-x".into()], ..TestArguments28::default() });
+
+        let iter = vec!["dummy", "--name", "-y"].into_iter().map(|x| x.to_string());
+        assert_eq!(TestArguments28::parse_args(iter), Err(jockey::Error::MissingValues("--name".into(), 1, 0)));
+
+        // Also if the value is not a known option.
+        let iter = vec!["dummy", "--name", "--foo"].into_iter().map(|x| x.to_string());
+        assert_eq!(TestArguments28::parse_args(iter), Err(jockey::Error::MissingValues("--name".into(), 1, 0)));
+
+        let iter = vec!["dummy", "--scale", "1", "--offset", "2"].into_iter().map(|x| x.to_string());
+        assert_eq!(TestArguments28::parse_args(iter), Err(jockey::Error::MissingValues("--scale".into(), 2, 1)));
+    }{
+        let iter = vec!["dummy", "-x", "--offset", "3"].into_iter().map(|x| x.to_string());
+        let actual = TestArguments29::parse_args(iter).unwrap();
+        assert_eq!(actual, TestArguments29 { one: false, offset: Some(3), first: Some("-x".into()) });
+
+        let iter = vec!["dummy", "x", "--offset", "-1"].into_iter().map(|x| x.to_string());
+        assert_eq!(TestArguments29::parse_args(iter), Err(jockey::Error::MissingValues("--offset".into(), 1, 0)));
+    }
+}
+
+#[derive(Arguments, Default, Debug, PartialEq)]
+pub struct TestArguments30 {
+    #[jockey(short_option="f")]
+    pub foo: Option<String>,
+
+    #[jockey(allow_hyphen_values)]
+    pub name: Option<String>,
+
+    #[jockey(duplicate_options="first_wins")]
+    pub level: Option<u32>,
+
+    pub tag: Vec<String>,
+}
+
+#[derive(Arguments, Default, Debug, PartialEq)]
+#[jockey(duplicate_options="last_wins")]
+pub struct TestArguments31 {
+    #[jockey(short_option="f")]
+    pub foo: Option<String>,
+
+    #[jockey(duplicate_options="error")]
+    pub level: Option<u32>,
+}
+
+#[test]
+pub fn parse_duplicate_options() {
+    {
+        let iter = vec!["dummy", "--foo", "a", "-f", "b"].into_iter().map(|x| x.to_string());
+        assert_eq!(TestArguments30::parse_args(iter), Err(jockey::Error::DuplicateOption("-f".into())));
+
+        let iter = vec!["dummy", "--foo", "a", "--foo=b"].into_iter().map(|x| x.to_string());
+        assert_eq!(TestArguments30::parse_args(iter), Err(jockey::Error::DuplicateOption("--foo".into())));
+
+        let iter = vec!["dummy", "--foo", "a", "--name", "--foo", "--level", "1", "--level", "2", "--tag", "x", "--tag", "y"].into_iter().map(|x| x.to_string());
+        let actual = TestArguments30::parse_args(iter).unwrap();
+        let expected = TestArguments30 { foo: Some("a".into()), name: Some("--foo".into()), level: Some(1), tag: vec!["x".into(), "y".into()] };
+        assert_eq!(actual, expected);
+    }{
+        let iter = vec!["dummy", "--foo", "a", "-f", "b", "--level", "1"].into_iter().map(|x| x.to_string());
+        let actual = TestArguments31::parse_args(iter).unwrap();
+        assert_eq!(actual, TestArguments31 { foo: Some("b".into()), level: Some(1) });
+
+        let iter = vec!["dummy", "--level", "1", "--level=2"].into_iter().map(|x| x.to_string());
+        assert_eq!(TestArguments31::parse_args(iter), Err(jockey::Error::DuplicateOption("--level".into())));
+
+        // Without allow_hyphen_values another option is not taken as the value.
+        let iter = vec!["dummy", "--foo", "--level", "1"].into_iter().map(|x| x.to_string());
+        assert_eq!(TestArguments31::parse_args(iter), Err(jockey::Error::MissingValues("--foo".into(), 1, 0)));
+
+        let iter = vec!["dummy", "--foo=--level", "--level", "1"].into_iter().map(|x| x.to_string());
+        assert_eq!(TestArguments31::parse_args(iter).unwrap(), TestArguments31 { foo: Some("--level".into()), level: Some(1) });
+    }
+}
+
+#[derive(Arguments, Default, Debug, PartialEq)]
+pub struct TestArguments32 {
+    #[jockey(default_value="localhost")]
+    pub endpoint: String,
+
+    #[jockey(short_option="s")]
+    pub size: Option<(u32, u32)>,
+
+    #[jockey(duplicate_options="first_wins")]
+    pub level: Option<u32>,
+
+    pub verbose: bool,
+
+    #[jockey(position=1)]
+    pub input: Option<String>,
+
+    #[jockey(unknown_args)]
+    pub rest: Vec<String>,
+
+    #[jockey(trailing_var_arg)]
+    pub command: Vec<String>,
+}
+
+#[test]
+pub fn parse_with_meta() {
+    use jockey::{FieldMeta, Source};
+
+    let iter = vec!["dummy", "in", "-s", "1", "2", "--level=1", "x", "--level", "2", "--", "cmd", "-x"].into_iter().map(|x| x.to_string());
+    let parsed = TestArguments32::parse_args_with_meta(iter).unwrap();
+    assert_eq!(parsed.endpoint, "localhost");
+    assert_eq!(parsed.level, Some(1));
+
+    assert_eq!(parsed.meta("endpoint"), Some(&FieldMeta { source: Source::DefaultValue, indices: vec![], occurrences: 0 }));
+    assert_eq!(parsed.meta("size"), Some(&FieldMeta { source: Source::Args, indices: vec![2, 3, 4], occurrences: 1 }));
+    assert_eq!(parsed.meta("level"), Some(&FieldMeta { source: Source::Args, indices: vec![5, 7, 8], occurrences: 2 }));
+    assert_eq!(parsed.meta("verbose"), Some(&FieldMeta { source: Source::Default, indices: vec![], occurrences: 0 }));
+    assert_eq!(parsed.meta("input"), Some(&FieldMeta { source: Source::Args, indices: vec![1], occurrences: 1 }));
+    assert_eq!(parsed.meta("rest"), Some(&FieldMeta { source: Source::Args, indices: vec![6], occurrences: 1 }));
+    assert_eq!(parsed.meta("command"), Some(&FieldMeta { source: Source::Args, indices: vec![10, 11], occurrences: 1 }));
+    assert_eq!(parsed.fields().count(), 7);
+
+    let args = parsed.into_inner();
+    assert_eq!(args.command, vec!["cmd".to_string(), "-x".to_string()]);
+}
+
+#[cfg(test)]
+fn round_trip<T: Arguments + ToArgs>(args: &T) -> Result<T> {
+    let iter = Some("dummy".to_string()).into_iter().chain(args.to_args());
+    T::parse_args(iter)
+}
+
+#[test]
+pub fn format_args() {
+    {
+        let actual = parse6(&vec!["dummy", "10.0.0.1", "--timeout", "10s", "--even", "2", "--even=4", "--intervals", "1s,2s"]).unwrap();
+        assert_eq!(actual.to_args(), vec!["10.0.0.1", "--timeout=10s", "--even=2", "--even=4", "--intervals=1s,2s"]);
+        assert_eq!(round_trip(&actual), Ok(actual));
+    }{
+        let actual = parse5(&vec!["dummy", "-D", "b=2", "-D", "a=1", "--label", "x=1", "--label", "x=2"]).unwrap();
+        assert_eq!(actual.to_args(), vec!["--define=a=1", "--define=b=2", "--label=x=1", "--label=x=2"]);
+        assert_eq!(round_trip(&actual), Ok(actual));
+    }{
+        let actual = parse9(&vec!["dummy", "--color", "--jobs", "file.txt"]).unwrap();
+        assert_eq!(actual.to_args(), vec!["--color=always", "--jobs", "file.txt"]);
+        assert_eq!(round_trip(&actual), Ok(actual));
+    }{
+        let actual = parse4(&vec!["dummy", "--tag", "a\\,b,c"]).unwrap();
+        assert_eq!(actual.to_args(), vec!["--tag=a\\,b,c"]);
+        assert_eq!(round_trip(&actual), Ok(actual));
+    }{
+        let mut actual = TestArguments11::default();
+        actual.enabled = Some(false);
+        actual.tag = vec!["".into()];
+        assert_eq!(actual.to_args(), vec!["", "--name=", "--enabled=false", "--count=0", "--tag="]);
+        assert_eq!(round_trip(&actual), Ok(actual));
+    }
+
+    let command = parse10(&vec!["dummy", "--resize", "800", "600"]).unwrap().to_command("convert");
+    assert_eq!(command.get_program(), "convert");
+    assert_eq!(command.get_args().collect::<Vec<_>>(), vec!["--resize=800", "600"]);
+}
+
+#[derive(Arguments, ToArgs, Default, Debug, Clone, PartialEq)]
+pub struct TestArguments11 {
+    pub name: String,
+
+    #[jockey(short_option="c")]
+    pub comment: Option<String>,
+
+    pub flag: bool,
+
+    pub enabled: Option<bool>,
+
+    pub count: u32,
+
+    pub offset: Option<i64>,
+
+    pub multi: Vec<String>,
+
+    #[jockey(value_delimiter=',')]
+    pub tag: Vec<String>,
+
+    #[jockey(short_option="D")]
+    pub define: HashMap<String, u32>,
+
+    pub resize: Option<(u32, u16)>,
+
+    pub level: Option<Level>,
+
+    pub jobs: Option<Option<u32>>,
+
+    #[jockey(position=1)]
+    pub command: String,
+
+    #[jockey(unknown_args)]
+    pub rest: Vec<String>,
+}
+
+/// Strings that do not start with a dash, so they cannot be taken for options.
+#[cfg(test)]
+fn arbitrary_word(g: &mut quickcheck::Gen) -> String {
+    let word = <String as quickcheck::Arbitrary>::arbitrary(g);
+    if word.starts_with('-') { format!("x{}", word) } else { word }
+}
+
+#[cfg(test)]
+impl quickcheck::Arbitrary for TestArguments11 {
+    fn arbitrary(g: &mut quickcheck::Gen) -> Self {
+        use quickcheck::Arbitrary;
+
+        let define: Vec<(String, u32)> = Arbitrary::arbitrary(g);
+        TestArguments11 {
+            name: Arbitrary::arbitrary(g),
+            comment: Arbitrary::arbitrary(g),
+            flag: Arbitrary::arbitrary(g),
+            enabled: Arbitrary::arbitrary(g),
+            count: Arbitrary::arbitrary(g),
+            offset: Arbitrary::arbitrary(g),
+            multi: Arbitrary::arbitrary(g),
+            tag: Arbitrary::arbitrary(g),
+            // Keys end at the first '='.
+            define: define.into_iter().map(|(key, value)| (key.replace('=', ""), value)).collect(),
+            resize: Arbitrary::arbitrary(g),
+            level: g.choose(&[None, Some(Level::Debug), Some(Level::Warn), Some(Level::HTTPTrace)]).cloned().unwrap(),
+            jobs: Arbitrary::arbitrary(g),
+            command: arbitrary_word(g),
+            rest: (0..g.size() % 4).map(|_| arbitrary_word(g)).collect(),
+        }
+    }
+}
+
+#[test]
+pub fn format_args_round_trip() {
+    fn property(args: TestArguments11) -> bool {
+        round_trip(&args) == Ok(args)
+    }
+    quickcheck::quickcheck(property as fn(TestArguments11) -> bool);
+}
+
+fn validate_landscape(size: &(u32, u32)) -> std::result::Result<(), String> {
+    if size.0 >= size.1 { Ok(()) } else { Err("must be landscape".into()) }
+}
+
+fn validate_lowercase_keys(map: &BTreeMap<String, u32>) -> std::result::Result<(), String> {
+    if map.keys().all(|key| key.to_lowercase() == *key) { Ok(()) } else { Err("keys must be lowercase".into()) }
+}
+
+#[derive(Arguments, Default, Debug, PartialEq)]
+pub struct TestArguments33 {
+    #[jockey(validate_with="validate_landscape")]
+    pub size: Option<(u32, u32)>,
+
+    #[jockey(validate_with="validate_lowercase_keys", duplicate_keys="error")]
+    pub set: BTreeMap<String, u32>,
+}
+
+#[test]
+pub fn parse_validated_tuple_and_map_args() {
+    {
+        let iter = vec!["dummy", "--size", "800", "600", "--set", "a=1", "--set=b=2"].into_iter().map(|x| x.to_string());
+        let actual = TestArguments33::parse_args(iter).unwrap();
+        let mut expected = TestArguments33::default();
+        expected.size = Some((800, 600));
+        expected.set.insert("a".into(), 1);
+        expected.set.insert("b".into(), 2);
+        assert_eq!(actual, expected);
+    }{
+        let iter = vec!["dummy", "--size", "600", "800"].into_iter().map(|x| x.to_string());
+        assert_eq!(TestArguments33::parse_args(iter), Err(jockey::Error::InvalidValue("--size".into(), "600 800".into(), "must be landscape".into())));
+    }{
+        let iter = vec!["dummy", "--set", "A=1"].into_iter().map(|x| x.to_string());
+        assert_eq!(TestArguments33::parse_args(iter), Err(jockey::Error::InvalidValue("--set".into(), "A=1".into(), "keys must be lowercase".into())));
+    }{
+        let iter = vec!["dummy", "--set", "a=1", "--set", "a=2"].into_iter().map(|x| x.to_string());
+        assert_eq!(TestArguments33::parse_args(iter), Err(jockey::Error::DuplicateKey("--set".into(), "a".into())));
+    }
+}
+
+/// Implements only `ParsableWithOption`, like types written before the other traits existed.
+#[derive(Debug, PartialEq)]
+pub struct Upper(String);
+
+impl jockey::ParsableWithOption for Upper {
+    fn parse_arg<I>(iter: &mut std::iter::Peekable<I>, option: &String) -> jockey::ParseResult<Self>
+        where I: Iterator<Item = (usize, String)>
+    {
+        <String as jockey::ParsableWithOption>::parse_arg(iter, option).map(|value| Upper(value.to_uppercase()))
+    }
+}
+
+#[derive(Arguments, Default, Debug, PartialEq)]
+pub struct TestArguments37 {
+    pub name: Option<Upper>,
+
+    pub names: Vec<Upper>,
+
+    pub level: Option<Level>,
+}
+
+#[test]
+pub fn parse_option_only_types() {
+    let iter = vec!["dummy", "--name", "x", "--names=a", "--names", "b"].into_iter().map(|x| x.to_string());
+    let actual = TestArguments37::parse_args(iter).unwrap();
+    assert_eq!(actual, TestArguments37 { name: Some(Upper("X".into())), names: vec![Upper("A".into()), Upper("B".into())], level: None });
+
+    let schema = TestArguments37::schema();
+    assert!(schema.options[0].possible_values.is_empty());
+    assert!(schema.options[1].possible_values.is_empty());
+    assert_eq!(schema.options[2].possible_values, vec!["debug", "info", "warning", "very-loud", "http-trace", "warn"]);
+}
@@ -10,8 +10,7 @@ struct MyArguments {
 }
 
 fn main() {
-    let args = <MyArguments as jockey::Arguments>::parse_args(std::env::args())
-        .expect("Error parsing command-line");
+    let args = <MyArguments as jockey::Arguments>::parse_or_exit();
 
     println!("{:#?}", args);
 }